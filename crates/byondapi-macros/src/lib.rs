@@ -9,6 +9,56 @@ fn extract_args(a: &syn::FnArg) -> &syn::PatType {
         _ => panic!("Not supported on types with `self`!"),
     }
 }
+
+/// Checks for `&MainThread`, by name since macros can't resolve types
+fn is_main_thread_ref(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(reference) if reference.mutability.is_none() => {
+            matches!(&*reference.elem, syn::Type::Path(path)
+                if path.path.segments.last().is_some_and(|segment| segment.ident == "MainThread"))
+        }
+        _ => false,
+    }
+}
+
+/// Checks for `ByondValue` anywhere in a type, by name since macros can't resolve types
+fn mentions_byond_value(ty: &syn::Type) -> bool {
+    fn search(tokens: proc_macro2::TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => ident == "ByondValue",
+            proc_macro2::TokenTree::Group(group) => search(group.stream()),
+            _ => false,
+        })
+    }
+    search(quote!(#ty))
+}
+
+/// Parses `#[bind]`'s attribute, which can have a proc path and/or `job`
+fn parse_bind_attr(attr: TokenStream) -> syn::Result<(Option<syn::LitStr>, bool)> {
+    let options = syn::parse::Parser::parse(
+        syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+        attr,
+    )?;
+    let mut proc = None;
+    let mut job = false;
+    for option in options {
+        match option {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: Lit::Str(path),
+                ..
+            }) if proc.is_none() => proc = Some(path),
+            syn::Expr::Path(path) if path.path.is_ident("job") && !job => job = true,
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "Bind attributes must be a string literal, `job`, or empty",
+                ))
+            }
+        }
+    }
+    Ok((proc, job))
+}
+
 //this is an example, mr clippy
 #[allow(clippy::test_attr_in_doctest)]
 /// Macro for generating byond binds
@@ -22,6 +72,40 @@ fn extract_args(a: &syn::FnArg) -> &syn::PatType {
 /// fn example_other(_: ByondValue, _: ByondValue) {Ok(ByondValue::null())}
 ///
 /// ```
+/// Arguments taken as `&mut T` are passed in from DM as pointers, like `example_pointer(&some_var)`.
+/// They're read and converted into a `T` before the call, and written back through the pointer after it succeeds.
+/// The wrapper generated in `bindings.dm` passes a pointer to its own argument when it's given a plain value, so
+/// `example_pointer(1)` works too, it just has nowhere to write back to.
+/// ```ignore
+/// #[byondapi::bind]
+/// fn example_pointer(counter: &mut f32) -> Result<ByondValue, byondapi::Error> {
+///     *counter += 1.0;
+///     Ok(ByondValue::null())
+/// }
+/// ```
+///
+/// With `job`, the body runs on a worker thread as a [job](https://docs.rs/byondapi/latest/byondapi/jobs/index.html)
+/// and DM gets the job's id back straight away. Arguments are converted to their types with `TryFrom<ByondValue>`
+/// on the main thread first, and the result is converted back into a `ByondValue` when DM collects it.
+/// Job binds can't take or return `ByondValue`s themselves, since those can only be used on the main thread. Take a
+/// [`RemoteValue`](https://docs.rs/byondapi/latest/byondapi/remote/struct.RemoteValue.html) instead.
+/// ```ignore
+/// #[byondapi::bind(job)]
+/// fn generate_caves(seed: f32, size: f32) -> Result<String, byondapi::Error> {
+///     Ok(generate(seed as u64, size as usize))
+/// }
+/// ```
+///
+/// Taking a `&MainThread` gets the bind a [main thread token](https://docs.rs/byondapi/latest/byondapi/main_thread/index.html).
+/// It isn't passed from DM, so it doesn't count as one of the proc's arguments.
+/// ```ignore
+/// #[byondapi::bind]
+/// fn example_token(main_thread: &MainThread, thing: ByondValue) -> Result<ByondValue, byondapi::Error> {
+///     let name = MainThreadValue::new(thing).get(main_thread).read_string("name")?;
+///     Ok(ByondValue::new_str(name)?)
+/// }
+/// ```
+///
 /// Then generate the bindings.dm file with
 /// ```
 /// #[test]
@@ -34,7 +118,10 @@ fn extract_args(a: &syn::FnArg) -> &syn::PatType {
 #[proc_macro_attribute]
 pub fn bind(attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(item as syn::ItemFn);
-    let proc = syn::parse_macro_input!(attr as Option<syn::Lit>);
+    let (proc, job) = match parse_bind_attr(attr) {
+        Ok(attr) => attr,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
     let func_name = &input.sig.ident;
     let func_name_disp = quote!(#func_name).to_string();
@@ -82,13 +169,114 @@ pub fn bind(attr: TokenStream, item: TokenStream) -> TokenStream {
         syn::Token![,],
     > = syn::punctuated::Punctuated::new();
 
+    let mut arg_prelude: Vec<proc_macro2::TokenStream> = Vec::new();
+    let mut pointer_writes: Vec<proc_macro2::TokenStream> = Vec::new();
+    let mut pointer_names: Vec<String> = Vec::new();
+
+    let mut takes_main_thread = false;
+
     for arg in args.iter().map(extract_args) {
+        //&MainThread args get the bind's token instead of a DM arg
+        if is_main_thread_ref(&arg.ty) {
+            if job {
+                return syn::Error::new(
+                    arg.ty.span(),
+                    "Job binds don't run on the main thread, so they can't take a MainThread",
+                )
+                .to_compile_error()
+                .into();
+            }
+            takes_main_thread = true;
+            proc_arg_unpacker.push(quote! { &__main_thread });
+            continue;
+        }
         if let syn::Pat::Ident(p) = &*arg.pat {
             arg_names.push(p.ident.clone());
             let index = arg_names.len() - 1;
-            proc_arg_unpacker.push(quote! {
-                args.get(#index).map(::byondapi::value::ByondValue::clone).unwrap_or_default()
-            });
+            match &*arg.ty {
+                syn::Type::Reference(reference) if job && reference.mutability.is_some() => {
+                    return syn::Error::new(
+                        reference.span(),
+                        "Pointer arguments are not supported in job binds",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+                ty if job && mentions_byond_value(ty) => {
+                    return syn::Error::new(
+                        ty.span(),
+                        "Job binds run on another thread, so they can't take ByondValues, use RemoteValue instead",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+                //Job binds convert their args on the main thread, the converted values are moved into the job
+                ty if job => {
+                    let value = Ident::new(&format!("__value_{}", p.ident), p.ident.span());
+                    arg_prelude.push(quote! {
+                        let #value: #ty = match ::std::convert::TryFrom::try_from(
+                            args.get(#index).map(::byondapi::value::ByondValue::clone).unwrap_or_default()
+                        ) {
+                            Ok(value) => value,
+                            Err(e) => {
+                                __report_error(::std::format!("{e:?}"));
+                                return ::byondapi::value::ByondValue::null();
+                            }
+                        };
+                    });
+                    proc_arg_unpacker.push(quote! { #value });
+                }
+                //&mut T args are passed in as pointers, which get read before the call and written back after
+                syn::Type::Reference(reference) if reference.mutability.is_some() => {
+                    let inner = &reference.elem;
+                    let pointer = Ident::new(&format!("__pointer_{}", p.ident), p.ident.span());
+                    let value = Ident::new(&format!("__value_{}", p.ident), p.ident.span());
+                    arg_prelude.push(quote! {
+                        let (#pointer, mut #value) = match ::byondapi::value::pointer::ByondPointer::<#inner>::new(
+                            args.get(#index).map(::byondapi::value::ByondValue::clone).unwrap_or_default()
+                        )
+                        .and_then(|pointer| Ok((pointer, pointer.read()?)))
+                        {
+                            Ok(read) => read,
+                            Err(e) => {
+                                __report_error(::std::format!("{e:?}"));
+                                return ::byondapi::value::ByondValue::null();
+                            }
+                        };
+                    });
+                    pointer_writes.push(quote! {
+                        if let Err(e) = #pointer.write(#value) {
+                            __report_error(::std::format!("{e:?}"));
+                        }
+                    });
+                    proc_arg_unpacker.push(quote! { &mut #value });
+                    pointer_names.push(p.ident.to_string());
+                }
+                _ => proc_arg_unpacker.push(quote! {
+                    args.get(#index).map(::byondapi::value::ByondValue::clone).unwrap_or_default()
+                }),
+            }
+        }
+    }
+
+    if takes_main_thread {
+        arg_prelude.insert(
+            0,
+            quote! {
+                //Safety: BYOND only calls binds on the main thread
+                let __main_thread = unsafe { ::byondapi::main_thread::MainThread::new_unchecked() };
+            },
+        );
+    }
+
+    if let syn::ReturnType::Type(_, ty) = func_return {
+        if job && mentions_byond_value(ty) {
+            return syn::Error::new(
+                ty.span(),
+                "Job binds run on another thread, so they can't return ByondValues",
+            )
+            .to_compile_error()
+            .into();
         }
     }
 
@@ -96,26 +284,19 @@ pub fn bind(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     //Submit to inventory
     let cthook_prelude = match &proc {
-        Some(Lit::Str(p)) => {
+        Some(p) => {
             quote! {
                 ::byondapi::inventory::submit!({
                     ::byondapi::binds::Bind {
                         proc_path: #p,
                         func_name: #func_name_ffi_disp,
                         func_arguments: #arg_names_disp,
+                        pointer_arguments: &[#(#pointer_names),*],
                         is_variadic: false,
                     }
                 });
             }
         }
-        Some(other_literal) => {
-            return syn::Error::new(
-                other_literal.span(),
-                "Bind attributes must be a string literal or empty",
-            )
-            .to_compile_error()
-            .into()
-        }
         None => {
             let mut func_name_disp = func_name_disp.clone();
             func_name_disp.insert_str(0, "/proc/");
@@ -125,6 +306,7 @@ pub fn bind(attr: TokenStream, item: TokenStream) -> TokenStream {
                         proc_path: #func_name_disp,
                         func_name: #func_name_ffi_disp,
                         func_arguments: #arg_names_disp,
+                        pointer_arguments: &[#(#pointer_names),*],
                         is_variadic: false,
                     }
                 });
@@ -132,25 +314,43 @@ pub fn bind(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
-    let result = quote! {
-        #cthook_prelude
-        #signature {
-            let args = unsafe { ::byondapi::parse_args(__argc, __argv) };
+    let call = if job {
+        quote! {
+            ::byondapi::value::ByondValue::from(
+                ::byondapi::jobs::spawn_job(move || #func_name(#proc_arg_unpacker))
+            )
+        }
+    } else {
+        quote! {
             match #func_name(#proc_arg_unpacker) {
-                Ok(val) => val,
+                Ok(val) => {
+                    #(#pointer_writes)*
+                    val
+                }
                 Err(e) => {
-                    let error_string = ::byondapi::value::ByondValue::try_from(::std::format!("{e:?}")).unwrap();
-                    ::byondapi::global_call::call_global_id({
-                            static STACK_TRACE: ::std::sync::OnceLock<u32> = ::std::sync::OnceLock::new();
-                            *STACK_TRACE.get_or_init(|| ::byondapi::byond_string::str_id_of("stack_trace")
-                                .expect("byondapi-rs implicitly expects stack_trace to exist as a proc for error reporting purposes, this proc doesn't exist!")
-                            )
-                        }
-                        ,&[error_string]).unwrap();
+                    __report_error(::std::format!("{e:?}"));
                     ::byondapi::value::ByondValue::null()
                 }
             }
+        }
+    };
 
+    let result = quote! {
+        #cthook_prelude
+        #signature {
+            let args = unsafe { ::byondapi::parse_args(__argc, __argv) };
+            let __report_error = |error: ::std::string::String| {
+                let error_string = ::byondapi::value::ByondValue::try_from(error).unwrap();
+                ::byondapi::global_call::call_global_id({
+                        static STACK_TRACE: ::std::sync::OnceLock<u32> = ::std::sync::OnceLock::new();
+                        *STACK_TRACE.get_or_init(|| ::byondapi::byond_string::str_id_of("stack_trace")
+                            .expect("byondapi-rs implicitly expects stack_trace to exist as a proc for error reporting purposes, this proc doesn't exist!")
+                        )
+                    }
+                    ,&[error_string]).unwrap();
+            };
+            #(#arg_prelude)*
+            #call
         }
         fn #func_name(#args) #func_return
         #body
@@ -221,6 +421,7 @@ pub fn bind_raw_args(attr: TokenStream, item: TokenStream) -> TokenStream {
                         proc_path: #p,
                         func_name: #func_name_ffi_disp,
                         func_arguments: "",
+                        pointer_arguments: &[],
                         is_variadic: true,
                     }
                 });
@@ -243,6 +444,7 @@ pub fn bind_raw_args(attr: TokenStream, item: TokenStream) -> TokenStream {
                             proc_path: #func_name_disp,
                             func_name: #func_name_ffi_disp,
                             func_arguments: "",
+                            pointer_arguments: &[],
                             is_variadic: true,
                         }
                     });
@@ -285,3 +487,120 @@ pub fn init(_: TokenStream, item: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// Macro for generating a live, typed proxy around a DM datum type
+/// Usage:
+/// ```ignore
+/// #[byondapi::byond_datum("/datum/reagents")]
+/// pub struct Reagents {
+///     pub total_volume: f32,
+///     pub maximum_volume: f32,
+/// }
+/// ```
+/// This replaces the struct with a wrapper around a `ByondValue`, with a getter and a `set_` setter
+/// for every field, which read and write the var of the same name on the wrapped datum each time they're called.
+/// Field types need to convert to and from `ByondValue`, failing with `byondapi::Error` if they can fail at all, so
+/// the conversion error is kept as the source of the one the accessor returns.
+///
+/// The wrapper is created with `Reagents::new(value)`, which fails if the value isn't a `/datum/reagents`.
+#[proc_macro_attribute]
+pub fn byond_datum(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as syn::ItemStruct);
+    let path = syn::parse_macro_input!(attr as syn::LitStr);
+
+    if !input.generics.params.is_empty() {
+        return syn::Error::new(
+            input.generics.span(),
+            "Generics are not supported on byond datum proxies",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let fields = match &input.fields {
+        syn::Fields::Named(fields) => &fields.named,
+        _ => {
+            return syn::Error::new(
+                input.span(),
+                "Byond datum proxies must be structs with named fields",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let struct_name = &input.ident;
+
+    let accessors = fields.iter().map(|field| {
+        let field_attrs = &field.attrs;
+        let field_vis = &field.vis;
+        let field_name = field.ident.as_ref().unwrap();
+        let field_type = &field.ty;
+        let var_name = field_name.to_string();
+        let setter_name = Ident::new(&format!("set_{var_name}"), field_name.span());
+        quote! {
+            #(#field_attrs)*
+            #field_vis fn #field_name(&self) -> ::std::result::Result<#field_type, ::byondapi::Error> {
+                let value = self.0.read_var_id(::byondapi::byond_string!(#var_name))?;
+                ::std::convert::TryFrom::try_from(value).map_err(|e| {
+                    ::byondapi::Error::from(e).with_context(
+                        ::byondapi::error::ErrorContext::new("read_var").name(#var_name).target(self.0),
+                    )
+                })
+            }
+
+            #(#field_attrs)*
+            #field_vis fn #setter_name(&mut self, value: #field_type) -> ::std::result::Result<(), ::byondapi::Error> {
+                let value: ::byondapi::value::ByondValue = ::std::convert::TryInto::try_into(value).map_err(|e| {
+                    ::byondapi::Error::from(e).with_context(
+                        ::byondapi::error::ErrorContext::new("write_var").name(#var_name).target(self.0),
+                    )
+                })?;
+                self.0.write_var_id(::byondapi::byond_string!(#var_name), &value)
+            }
+        }
+    });
+
+    quote! {
+        #(#attrs)*
+        #vis struct #struct_name(::byondapi::value::ByondValue);
+
+        impl #struct_name {
+            /// The DM type path this proxy wraps
+            pub const PATH: &'static str = #path;
+
+            /// Wraps a value, failing if it isn't of this type path
+            pub fn new(value: ::byondapi::value::ByondValue) -> ::std::result::Result<Self, ::byondapi::Error> {
+                if value.is_type(Self::PATH)? {
+                    Ok(Self(value))
+                } else {
+                    Err(::byondapi::Error::NotOfType(value, Self::PATH.to_owned()))
+                }
+            }
+
+            /// Gets the wrapped value
+            pub fn as_value(&self) -> &::byondapi::value::ByondValue {
+                &self.0
+            }
+
+            #(#accessors)*
+        }
+
+        impl ::std::convert::TryFrom<::byondapi::value::ByondValue> for #struct_name {
+            type Error = ::byondapi::Error;
+
+            fn try_from(value: ::byondapi::value::ByondValue) -> ::std::result::Result<Self, Self::Error> {
+                Self::new(value)
+            }
+        }
+
+        impl ::std::convert::From<#struct_name> for ::byondapi::value::ByondValue {
+            fn from(value: #struct_name) -> Self {
+                value.0
+            }
+        }
+    }
+    .into()
+}
@@ -1,6 +1,20 @@
 #![allow(clippy::missing_safety_doc)]
 
-use byondapi::{byond_string, map::*, prelude::*};
+use byondapi::{
+    byond_string,
+    command_buffer::*,
+    dmm::*,
+    global_var::*,
+    main_thread::*,
+    map::{chunks::*, flood_fill::*, grid::*, los::*, pathfinding::*, pipeline::*, range::*, *},
+    prelude::*,
+    queue::*,
+    remote::*,
+    task::*,
+    threadsync::*,
+    tick_budget::*,
+    world::*,
+};
 use eyre::Result;
 
 #[test]
@@ -224,3 +238,920 @@ fn test_new_obj() -> Result<ByondValue> {
         &[],
     )?)
 }
+
+#[byondapi::bind]
+fn test_call_sleeping(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    object.call_sleeping("get_name_sleeping", &[], move |result| {
+        let mut object = object;
+        object.write_var("sleep_result", &result.unwrap()).unwrap();
+    })?;
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_call_sleeping_killed(object: ByondValue, holder: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    object.call_sleeping("get_name_sleeping", &[], move |result| {
+        let mut holder = holder;
+        let outcome = if result.is_err() {
+            "killed"
+        } else {
+            "returned"
+        };
+        holder
+            .write_var("sleep_result", &outcome.try_into().unwrap())
+            .unwrap();
+    })?;
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_introspection(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    assert!(object.has_proc("get_test_name")?);
+    assert!(!object.has_proc("not_a_proc")?);
+
+    assert!(object.has_var("test_name")?);
+    assert!(!object.has_var("not_a_var")?);
+
+    let names = object.var_names()?;
+    assert!(names.iter().any(|name| name == "test_name"));
+
+    assert_eq!(object.read_string("test_name")?, "changed");
+    assert_eq!(object.initial_var("test_name")?.get_string()?, "dust");
+
+    Ok(Default::default())
+}
+
+#[byondapi::byond_datum("/datum/testobject")]
+struct TestObject {
+    test_name: String,
+}
+
+#[byondapi::bind]
+fn test_datum_proxy(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    assert!(TestObject::new(ByondValue::new_list()?).is_err());
+
+    let mut object = TestObject::new(object)?;
+    assert_eq!(object.test_name()?, "dust");
+    object.set_test_name("proxied".to_owned())?;
+
+    Ok(object.into())
+}
+
+#[byondapi::bind]
+fn test_globals() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    assert_eq!(read_global("test_global")?.get_number()?, 5.0);
+    assert_eq!(
+        read_global_id(byond_string!("test_global"))?.get_number()?,
+        5.0
+    );
+    assert!(global_var_names()?.iter().any(|name| name == "test_global"));
+
+    write_global("test_global", &ByondValue::new_num(10.0))?;
+
+    Ok(ByondValue::world())
+}
+
+#[byondapi::bind]
+fn test_world() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let mut world = World::new();
+
+    world.set_maxx(3)?;
+    world.set_maxy(3)?;
+    world.set_maxz(1)?;
+    assert_eq!(world.maxx()?, 3);
+    assert_eq!(world.maxz()?, 1);
+
+    let expected = if cfg!(windows) {
+        SystemType::MsWindows
+    } else {
+        SystemType::Unix
+    };
+    assert_eq!(world.system_type()?, expected);
+
+    world.tick_usage()?;
+    world.time()?;
+
+    Ok(world.tick_lag()?.into())
+}
+
+#[byondapi::bind]
+fn test_ptr_typed(counter: &mut f32, name: &mut String) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    *counter += 1.0;
+    name.insert_str(0, "awa");
+
+    Ok((*counter).into())
+}
+
+#[byondapi::bind]
+fn test_xyz() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let corner1 = ByondXYZ::with_coords((1, 1, 1));
+    let corner2 = corner1 + (1, 2, 0);
+    assert_eq!(corner2.coordinates(), (2, 3, 1));
+    assert_eq!(corner2 - corner1, (1, 2, 0));
+    assert_eq!(corner1.get_dist(&corner2), 2);
+    assert_eq!(corner1.manhattan_dist(&corner2), 3);
+    let (far1, far2) = (
+        ByondXYZ::with_coords((i16::MIN, i16::MIN, 1)),
+        ByondXYZ::with_coords((i16::MAX, i16::MAX, 1)),
+    );
+    assert!((far1.euclidean_dist(&far2) - 65535.0 * std::f32::consts::SQRT_2).abs() < 1.0);
+
+    let coords = corner2.range_to(&corner1).collect::<Vec<_>>();
+    assert_eq!(coords.len(), 6);
+    assert!(coords.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let turfs = locate_many(&coords)?;
+    assert_eq!(turfs, byond_block(corner1, corner2)?);
+    assert_eq!(xyz_many(&turfs)?, coords);
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_block_chunks() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let everything = byond_block(
+        ByondXYZ::with_coords((1, 1, 1)),
+        ByondXYZ::with_coords((5, 5, 1)),
+    )?;
+
+    let mut chunk_count = 0;
+    let mut turfs = Vec::new();
+    BlockChunks::zlevel(1, ChunkShape::Tiles(2))?.for_each_chunk(|chunk| {
+        chunk_count += 1;
+        turfs.extend_from_slice(chunk.turfs);
+        Ok(())
+    })?;
+    assert_eq!(chunk_count, 9);
+    assert_eq!(turfs.len(), everything.len());
+    assert!(everything.iter().all(|turf| turfs.contains(turf)));
+
+    let mut rows = BlockChunks::world(ChunkShape::Rows)?;
+    let mut row_turfs = Vec::new();
+    while let Some(chunk) = rows.next_chunk() {
+        let chunk = chunk?;
+        assert_eq!(chunk.min.y(), chunk.max.y());
+        row_turfs.extend_from_slice(chunk.turfs);
+    }
+    assert_eq!(row_turfs, everything);
+
+    // Tiles bigger than the region are shrunk to it
+    let mut single = BlockChunks::zlevel(1, ChunkShape::Tiles(i16::MAX))?;
+    assert_eq!(single.next_chunk().unwrap()?.turfs, everything.as_slice());
+    assert!(single.next_chunk().is_none());
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_empty_world() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    assert!(BlockChunks::world(ChunkShape::Rows)?.next_chunk().is_none());
+    assert!(BlockChunks::zlevel(1, ChunkShape::ZLevels)?
+        .next_chunk()
+        .is_none());
+
+    let mut open = |_| Some(1.0);
+    let (start, goal) = (
+        ByondXYZ::with_coords((1, 1, 1)),
+        ByondXYZ::with_coords((2, 1, 1)),
+    );
+    assert!(astar_path(start, goal, &mut open, &PathOptions::default())?.is_none());
+
+    let mut clear = |_| false;
+    assert!(field_of_view(start, 3, &mut clear)?.is_empty());
+
+    assert!(flood_fill(start, None, |_, _| true)?.members.is_empty());
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_ranges(thing: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let center = ByondXYZ::with_coords((3, 3, 1));
+    let center_turf = byond_locatexyz(center)?;
+
+    let range = range_turfs(center, 2)?;
+    assert_eq!(range.len(), 25);
+    assert_eq!(
+        range,
+        byond_block(
+            ByondXYZ::with_coords((1, 1, 1)),
+            ByondXYZ::with_coords((5, 5, 1))
+        )?
+    );
+
+    let orange = orange_turfs(center, 2)?;
+    assert_eq!(orange.len(), 24);
+    assert!(!orange.contains(&center_turf));
+
+    assert_eq!(range_turfs(ByondXYZ::with_coords((1, 1, 1)), 1)?.len(), 4);
+    assert!(range_turfs(ByondXYZ::with_coords((1, 1, 2)), 1)?.is_empty());
+
+    // Radius 2 cuts off the four corners of the square
+    let circle = circle_range_turfs(center, 2)?;
+    assert_eq!(circle.len(), 21);
+    assert!(!circle.contains(&byond_locatexyz(ByondXYZ::with_coords((1, 1, 1)))?));
+
+    let atoms = range_atoms(center, 0)?;
+    assert_eq!(atoms, vec![center_turf, thing]);
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_pathfinding() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let start = ByondXYZ::with_coords((1, 1, 1));
+    let goal = ByondXYZ::with_coords((5, 1, 1));
+
+    // A wall down x = 3 with a gap at the top
+    let mut grid = CostGrid::from_turfs(start, ByondXYZ::with_coords((5, 5, 1)), |_| Some(1.0))?;
+    for y in 1..=4 {
+        grid.set(ByondXYZ::with_coords((3, y, 1)), None);
+    }
+
+    let options = PathOptions::default();
+    let astar = astar_path(start, goal, &mut grid, &options)?.unwrap();
+    let jps = jps_path(start, goal, &mut grid, &options)?.unwrap();
+    assert_eq!(astar.len(), 8);
+    assert_eq!(jps.len(), astar.len());
+    assert_eq!(jps.last(), Some(&goal));
+    assert!(jps.contains(&ByondXYZ::with_coords((3, 5, 1))));
+
+    let no_diagonals = PathOptions {
+        diagonals: DiagonalMode::Never,
+        ..Default::default()
+    };
+    assert_eq!(
+        jps_path(start, goal, &mut grid, &no_diagonals)?
+            .unwrap()
+            .len(),
+        12
+    );
+
+    let too_short = PathOptions {
+        max_distance: Some(7),
+        ..Default::default()
+    };
+    assert!(astar_path(start, goal, &mut grid, &too_short)?.is_none());
+
+    // Going round through y = 2 is cheaper than walking along y = 1, but takes too many steps
+    let mut costly_row = |xyz: ByondXYZ| Some(if xyz.y() == 1 { 10.0 } else { 1.0 });
+    let limited = PathOptions {
+        diagonals: DiagonalMode::Never,
+        max_distance: Some(4),
+        ..Default::default()
+    };
+    let straight = astar_path(start, goal, &mut costly_row, &limited)?.unwrap();
+    assert!(straight.iter().all(|xyz| xyz.y() == 1));
+
+    // Closures search the whole world
+    let mut open = |_| Some(1.0);
+    let straight = astar_path(start, goal, &mut open, &options)?.unwrap();
+    let turfs = locate_many(&straight)?;
+    assert_eq!(turfs.len(), 4);
+    assert_eq!(turfs.last(), Some(&byond_locatexyz(goal)?));
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_turf_grid() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let mut grid = TurfGrid::<u32>::new()?;
+    assert_eq!(grid.size(), (5, 5, 1));
+    assert!(!grid.refresh()?);
+
+    for (xyz, turf, data) in grid.iter() {
+        assert_eq!(turf, byond_locatexyz(xyz)?);
+        assert_eq!(*data, 0);
+    }
+    assert!(grid.turf_at(ByondXYZ::with_coords((6, 1, 1))).is_none());
+
+    let kept = ByondXYZ::with_coords((2, 3, 1));
+    grid.set_data(kept, 7);
+
+    World::new().set_maxx(6)?;
+    assert!(grid.refresh()?);
+    assert_eq!(grid.size(), (6, 5, 1));
+    assert_eq!(grid.data(kept), Some(&7));
+
+    let new_turf = ByondXYZ::with_coords((6, 1, 1));
+    assert_eq!(grid.turf_at(new_turf), Some(byond_locatexyz(new_turf)?));
+    assert_eq!(grid.data(new_turf), Some(&0));
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_ref_strings(thing: ByondValue, dm_ref: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let dm_ref = dm_ref.get_string()?;
+    assert_eq!(thing.to_ref_string()?, dm_ref);
+    assert_eq!(ByondValue::from_ref_string(&dm_ref)?, thing);
+
+    let id: RefId = dm_ref.parse()?;
+    assert_eq!(id, thing.ref_id()?);
+    assert_eq!(id.id(), thing.get_ref()?);
+    assert_eq!(id.to_string(), dm_ref);
+    assert_eq!(
+        "0x2000001".parse::<RefId>()?,
+        RefId::new(ValueType::Obj as u8, 1)
+    );
+    assert!("not a ref".parse::<RefId>().is_err());
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_line_of_sight() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    // A wall down x = 3 with a gap at the top
+    let mut wall = |xyz: ByondXYZ| xyz.x() == 3 && xyz.y() < 5;
+    let center = ByondXYZ::with_coords((1, 3, 1));
+    let behind_wall = ByondXYZ::with_coords((5, 3, 1));
+
+    assert_eq!(line(center, behind_wall).len(), 5);
+    assert!(has_line_of_sight(
+        center,
+        ByondXYZ::with_coords((3, 3, 1)),
+        &mut wall
+    )?);
+    assert!(!has_line_of_sight(center, behind_wall, &mut wall)?);
+    assert_eq!(
+        raycast(center, behind_wall, &mut wall)?,
+        Some(ByondXYZ::with_coords((3, 3, 1)))
+    );
+
+    let view = field_of_view(center, 4, &mut wall)?;
+    assert!(view.contains(&center));
+    assert!(view.contains(&ByondXYZ::with_coords((3, 3, 1))));
+    assert!(!view.contains(&behind_wall));
+    for y in 1..=5 {
+        assert!(view.contains(&ByondXYZ::with_coords((2, y, 1))));
+    }
+
+    let mut grid = TurfGrid::<bool>::new()?;
+    for (x, y) in [(3, 1), (3, 2), (3, 3), (3, 4)] {
+        grid.set_data(ByondXYZ::with_coords((x, y, 1)), true);
+    }
+    assert_eq!(field_of_view(center, 4, &mut grid)?, view);
+
+    let turfs = visible_turfs(center, 4, &mut grid)?;
+    assert_eq!(turfs, locate_many(&view)?);
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_thread_sync(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let mut object = object;
+    std::thread::spawn(move || {
+        let synced = thread_sync_blocking(|| 21.0);
+        let doubled = thread_sync_deferred(|| 21 * 2).wait();
+        let _ = thread_sync_blocking(move || {
+            let passed = synced.is_ok_and(|value| value.get_number().is_ok_and(|n| n == 21.0))
+                && doubled.is_ok_and(|n| n == 42);
+            object.write_var("thread_result", &passed.into()).is_ok()
+        });
+    });
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_spawn_main(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let mut object = object;
+    // Spawned from another thread, so everything has to hop back onto the main thread
+    std::thread::spawn(move || {
+        spawn_main(async move {
+            let name = object.call_future("get_name_sleeping", &[])?.await?;
+            let doubled = main_thread(|| 21 * 2).await?;
+            let passed = name.get_string()? == "sleepy" && doubled == 42;
+            object.write_var("task_result", &passed.into())
+        })
+    });
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_spawn_main_nested(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let mut object = object;
+    // The inner task finishes after the outer one is already waiting on it
+    spawn_main(async move {
+        let name = spawn_main(object.call_future("get_name_sleeping", &[])?).await??;
+        object.write_var("task_result", &(name.get_string()? == "sleepy").into())
+    });
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_remote_value(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let remote = RemoteValue::new(object);
+    // Runs straight away on the main thread, instead of waiting for it to be free
+    let name: String = remote.read_var("name")?;
+    assert_eq!(name, "remote");
+
+    std::thread::spawn(move || {
+        let checks = || -> std::result::Result<bool, byondapi::Error> {
+            let name: String = remote.read_var("name")?;
+            remote.write_var("name", "renamed")?;
+
+            let list: RemoteValue = remote.read_var("remote_list")?;
+            let items: Vec<f32> = list.get_list()?;
+            list.write_list_index(2.0, 20.0)?;
+            let second: f32 = list.read_list_index(2.0)?;
+
+            let sum: f32 = remote.call("remote_add", &[1.0.into(), 2.0.into()])?;
+            Ok(name == "remote" && items == [1.0, 2.0, 3.0] && second == 20.0 && sum == 3.0)
+        };
+        let passed = checks().unwrap_or(false);
+        let _ = remote.write_var("remote_result", passed);
+    });
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind(job)]
+fn test_job(a: f32, b: f32) -> Result<f32> {
+    Ok(a + b)
+}
+
+#[byondapi::bind(job)]
+fn test_job_endless() -> Result<f32> {
+    loop {
+        byondapi::jobs::check_cancelled()?;
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[byondapi::bind]
+fn test_evict_jobs() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    Ok((byondapi::jobs::evict_uncollected(std::time::Duration::ZERO) as f32).into())
+}
+
+/// Kept alive between the test bind and DM draining it
+static TEST_QUEUE: std::sync::Mutex<Option<MainThreadQueue<f32>>> = std::sync::Mutex::new(None);
+
+#[byondapi::bind]
+fn test_main_thread_queue(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let mut target = object;
+    let queue = MainThreadQueue::new(4, move |amount: f32| {
+        let total = target.read_number("queue_total")?;
+        target.write_var("queue_total", &(total + amount).into())
+    });
+    queue.set_budget(DrainBudget {
+        max_messages: Some(2),
+        max_time: None,
+    });
+
+    for amount in [1.0, 2.0, 3.0, 4.0] {
+        assert!(queue.try_push(amount).is_ok());
+    }
+    assert_eq!(queue.try_push(5.0), Err(5.0));
+    assert_eq!(queue.drain()?, 2);
+    assert_eq!(object.read_number("queue_total")?, 3.0);
+    assert_eq!(queue.len(), 2);
+
+    *TEST_QUEUE.lock().unwrap() = Some(queue);
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_main_thread_queue_auto_drain() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let queue = TEST_QUEUE.lock().unwrap().take().unwrap();
+    for amount in [1.0, 2.0, 3.0, 4.0] {
+        assert!(queue.try_push(amount).is_ok());
+    }
+    // Only the budget's worth is handled straight away, the rest waits for the next tick
+    queue.set_auto_drain(true);
+    let left = queue.len();
+    std::thread::spawn(move || queue.push(5.0));
+    Ok((left as f32).into())
+}
+
+#[byondapi::bind]
+fn test_main_thread_token(main_thread: &MainThread, object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let name = MainThreadValue::new(object)
+        .get(main_thread)
+        .read_var("name")?;
+    if name.get_string()? != "tokened" {
+        return Err(eyre::eyre!("Couldn't read through the token"));
+    }
+    if MainThread::current().is_none() {
+        return Err(eyre::eyre!("The main thread wasn't recognised"));
+    }
+
+    let object = MainThreadValue::new(object);
+    std::thread::spawn(move || {
+        let off_main_thread = MainThread::current().is_none();
+        let _ = thread_sync_with_token(move |main_thread| {
+            let mut object = object.get(main_thread);
+            object
+                .write_var("token_result", &off_main_thread.into())
+                .is_ok()
+        });
+    });
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_command_buffer(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let remote = RemoteValue::new(object);
+    std::thread::spawn(move || {
+        let passed = (|| -> Result<bool> {
+            let list: RemoteValue = remote.read_var("buffer_list")?;
+
+            let mut buffer = CommandBuffer::new();
+            buffer.write_var(remote.clone(), "name", "buffered");
+            buffer.write_list_index(list, 2.0, 20.0);
+            let item = buffer.new_instance("/obj", &[]);
+            buffer.write_var(item, "name", "made");
+            buffer.write_var(remote.clone(), "buffer_item", item);
+            let sum = buffer.call(remote.clone(), "remote_add", &[1.0.into(), 2.0.into()]);
+            let missing = buffer.call(remote.clone(), "buffer_missing_proc", &[]);
+            let after_missing = buffer.write_var(missing, "name", "unreachable");
+
+            let results = buffer.apply()?;
+            Ok(results.len() == 8
+                && matches!(results[sum.index()], Ok(RemoteArg::Number(n)) if n == 3.0)
+                && matches!(results[item.index()], Ok(RemoteArg::Remote(_)))
+                && results[missing.index()].is_err()
+                && matches!(
+                    results[after_missing.index()].as_ref().map_err(byondapi::Error::root_cause),
+                    Err(byondapi::Error::CommandResultUnavailable(index)) if *index == missing.index()
+                ))
+        })()
+        .unwrap_or(false);
+        let _ = remote.write_var("buffer_result", passed);
+    });
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_tick_budget() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    if !TickBudget::new(0.0)?.should_yield() {
+        return Err(eyre::eyre!("An empty budget didn't run out"));
+    }
+    if TickBudget::new(1000.0)?.should_yield() {
+        return Err(eyre::eyre!("A huge budget ran out straight away"));
+    }
+    Ok(Default::default())
+}
+
+thread_local! {
+    /// Kept between starting the run and DM checking on it
+    static CHUNKED_RUN: std::cell::RefCell<Option<ChunkedRun>> = const { std::cell::RefCell::new(None) };
+}
+
+#[byondapi::bind]
+fn test_run_chunked(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let mut object = object;
+    let mut total = 0.0;
+    let run = run_chunked(1..=20, 0.0, move |i| {
+        total += i as f32;
+        object.write_var("chunked_total", &total.into())
+    })?;
+    // An empty budget still gets through one item a tick
+    if run.per_tick() != [1] || run.is_finished() {
+        return Err(eyre::eyre!("Unexpected first tick {:?}", run.per_tick()));
+    }
+    CHUNKED_RUN.with_borrow_mut(|stored| *stored = Some(run));
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_run_chunked_ticks() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let run = CHUNKED_RUN.with_borrow_mut(Option::take).unwrap();
+    if !run.is_finished() || run.failed() || run.processed() != 20 {
+        return Err(eyre::eyre!("Chunked run didn't finish cleanly"));
+    }
+    let per_tick = run.per_tick();
+    if per_tick.iter().any(|&processed| processed > 1) {
+        return Err(eyre::eyre!("An empty budget ran more than one item a tick"));
+    }
+    // The last tick finds nothing left, so only count ticks that did something
+    Ok((per_tick.iter().filter(|&&processed| processed > 0).count() as f32).into())
+}
+
+#[byondapi::bind]
+fn test_error_context(object: ByondValue) -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let error = object.call("error_context_missing_proc", &[]).unwrap_err();
+    assert!(matches!(
+        error.root_cause(),
+        byondapi::Error::InvalidProc(_)
+    ));
+    let context = error.context().unwrap();
+    assert_eq!(context.operation, "call");
+    assert_eq!(context.name.as_deref(), Some("error_context_missing_proc"));
+    assert!(context.target.is_some());
+    assert!(std::error::Error::source(&error).is_some());
+    assert!(error
+        .to_string()
+        .starts_with("call error_context_missing_proc on"));
+
+    let error = ByondValue::new_num(1.0).read_var("name").unwrap_err();
+    assert!(matches!(
+        error.root_cause(),
+        byondapi::Error::NotReferencable(_)
+    ));
+    assert_eq!(error.context().unwrap().operation, "read_var");
+
+    // Names given as string ids are shown as the string
+    let error = ByondValue::new_num(1.0)
+        .read_var_id(byond_string!("name"))
+        .unwrap_err();
+    assert_eq!(error.context().unwrap().name.as_deref(), Some("name"));
+
+    let error = ByondValue::new_num(1.0).get_list_values().unwrap_err();
+    assert!(matches!(error.root_cause(), byondapi::Error::NotAList(_)));
+    assert_eq!(error.context().unwrap().operation, "get_list_values");
+    let error = ByondValue::new_num(1.0).read_list_index(1.0).unwrap_err();
+    assert!(matches!(error.root_cause(), byondapi::Error::NotAList(_)));
+    assert_eq!(error.context().unwrap().operation, "read_list_index");
+    let error = ByondValue::null().get_number().unwrap_err();
+    assert_eq!(error.context().unwrap().operation, "get_number");
+
+    match "nope".parse::<RefId>() {
+        Err(byondapi::Error::InvalidConversion { expected, actual }) => {
+            assert_eq!(expected, "ref");
+            assert_eq!(actual, "\"nope\"");
+        }
+        _ => return Err(eyre::eyre!("Parsing a bad ref didn't fail as expected")),
+    }
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_flood_fill() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    // A wall down x = 3, splitting the world in two
+    let open = |xyz: ByondXYZ| xyz.x() != 3;
+    let connected = |from: ByondXYZ, to: ByondXYZ| open(from) && open(to);
+    let wall: Vec<ByondXYZ> = (1..=5).map(|y| ByondXYZ::with_coords((3, y, 1))).collect();
+
+    let left = flood_fill(ByondXYZ::with_coords((1, 1, 1)), None, connected)?;
+    assert_eq!(left.members.len(), 10);
+    assert!(left.contains(ByondXYZ::with_coords((2, 5, 1))));
+    assert!(!left.contains(ByondXYZ::with_coords((4, 1, 1))));
+    assert_eq!(left.boundary, wall);
+    assert_eq!(left.boundary_turfs()?, locate_many(&wall)?);
+
+    let regions = connected_regions(
+        ByondXYZ::with_coords((1, 1, 1)),
+        ByondXYZ::with_coords((5, 5, 1)),
+        connected,
+    )?;
+    assert_eq!(regions.len(), 7);
+    assert_eq!(regions[0], left);
+    assert_eq!(regions[1].members, vec![ByondXYZ::with_coords((3, 1, 1))]);
+    assert_eq!(regions[2].members.len(), 10);
+    assert_eq!(regions[2].boundary, wall);
+
+    // With a gap at the top, both sides are one region
+    let with_gap = flood_fill(ByondXYZ::with_coords((1, 1, 1)), None, |_, to: ByondXYZ| {
+        to.x() != 3 || to.y() == 5
+    })?;
+    assert_eq!(with_gap.members.len(), 21);
+    assert_eq!(with_gap.turfs()?, locate_many(&with_gap.members)?);
+
+    Ok(Default::default())
+}
+
+#[derive(Clone, PartialEq)]
+struct Heat(f32);
+
+impl CellState for Heat {
+    fn read(turf: &ByondValue) -> std::result::Result<Self, byondapi::Error> {
+        Ok(Heat(turf.read_number("heat")?))
+    }
+
+    fn write(
+        &self,
+        _old: &Self,
+        turf: &mut ByondValue,
+    ) -> std::result::Result<(), byondapi::Error> {
+        turf.write_var("heat", &self.0.into())
+    }
+}
+
+#[byondapi::bind]
+fn test_region_pipeline() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let heat_at =
+        |xyz: (i16, i16, i16)| byond_locatexyz(ByondXYZ::with_coords(xyz))?.read_number("heat");
+    let mut pipeline = RegionPipeline::<Heat>::new(
+        ByondXYZ::with_coords((1, 1, 1)),
+        ByondXYZ::with_coords((5, 5, 1)),
+    )?;
+    assert!(pipeline.state(ByondXYZ::with_coords((3, 3, 1))) == Some(&Heat(100.0)));
+    pipeline.set_write_budget(Some(2));
+
+    // Heat spreads to the four cells around the middle
+    let spread = |cell: CellView<'_, Heat>| {
+        let hottest = cell
+            .neighbours()
+            .map(|heat| heat.0)
+            .fold(cell.state().0, f32::max);
+        Heat(hottest)
+    };
+    pipeline.step(spread);
+    assert_eq!(pipeline.pending_writes(), 4);
+    assert_eq!(pipeline.write_back()?, 2);
+    assert_eq!(pipeline.pending_writes(), 2);
+    assert_eq!(pipeline.write_back()?, 2);
+    assert_eq!(pipeline.write_back()?, 0);
+    assert_eq!(heat_at((3, 4, 1))?, 100.0);
+    assert_eq!(heat_at((2, 3, 1))?, 100.0);
+    assert_eq!(heat_at((2, 2, 1))?, 0.0);
+
+    // Only cells that changed since they were written count
+    pipeline.step(spread);
+    assert_eq!(pipeline.pending_writes(), 8);
+
+    // With every cell changing each step and a budget smaller than that, later steps write the cells earlier ones
+    // didn't get to
+    let mut churn = RegionPipeline::<Heat>::new(
+        ByondXYZ::with_coords((1, 1, 1)),
+        ByondXYZ::with_coords((5, 5, 1)),
+    )?;
+    churn.set_write_budget(Some(10));
+    let (fourth_row_end, last) = ((5, 4, 1), (5, 5, 1));
+    let (fourth_row_before, last_before) = (heat_at(fourth_row_end)?, heat_at(last)?);
+    let warm = |cell: CellView<'_, Heat>| Heat(cell.state().0 + 1.0);
+    for _ in 0..2 {
+        churn.step(warm);
+        assert_eq!(churn.write_back()?, 10);
+    }
+    assert_eq!(heat_at(fourth_row_end)?, fourth_row_before + 2.0);
+    assert_eq!(heat_at(last)?, last_before);
+    churn.step(warm);
+    churn.write_back()?;
+    assert_eq!(heat_at(last)?, last_before + 3.0);
+
+    // Corners off the map are clamped to it
+    let clamped = RegionPipeline::<Heat>::new(
+        ByondXYZ::with_coords((-5, -5, 0)),
+        ByondXYZ::with_coords((3, 3, 1)),
+    )?;
+    assert!(clamped.state(ByondXYZ::with_coords((3, 3, 1))) == Some(&Heat(100.0)));
+    assert!(clamped.state(ByondXYZ::with_coords((0, 1, 1))).is_none());
+    let off_map = RegionPipeline::<Heat>::new(
+        ByondXYZ::with_coords((-5, -5, 1)),
+        ByondXYZ::with_coords((-1, -1, 1)),
+    )?;
+    assert!(off_map.state(ByondXYZ::with_coords((1, 1, 1))).is_none());
+
+    Ok(Default::default())
+}
+
+#[byondapi::bind]
+fn test_dmm() -> Result<ByondValue> {
+    setup_panic_handler();
+
+    let test_map = include_str!("../dm_project/test_map.dmm");
+    let parsed: Dmm = test_map.parse()?;
+    assert_eq!(parsed.size(), (10, 10, 1));
+    assert_eq!(parsed.to_string(), test_map.replace("\r\n", "\n"));
+
+    let tgm_map = r#"//MAP CONVERTED BY dmm2tgm.py THIS HEADER COMMENT PREVENTS RECONVERSION, DO NOT REMOVE
+"a" = (
+/obj/dmm_thing{
+	name = "placed";
+	dmm_list = list("x" = 1)
+	},
+/turf/turf_type_a,
+/area)
+"b" = (
+/turf/turf_type_b,
+/area)
+
+(1,1,1) = {"
+a
+b
+"}
+(2,1,1) = {"
+b
+a
+"}
+"#;
+    let tgm: Dmm = tgm_map.parse()?;
+    assert_eq!(tgm.format, DmmFormat::Tgm);
+    assert_eq!(tgm.size(), (2, 2, 1));
+    assert_eq!(tgm.to_string(), tgm_map);
+    assert_eq!(
+        tgm.tile_at(ByondXYZ::with_coords((1, 2, 1)))
+            .unwrap()
+            .prefabs[0]
+            .var("name"),
+        Some(&DmValue::String("placed".to_owned()))
+    );
+
+    // Maps can't hold NaN or infinity
+    assert_eq!(DmValue::Number(f32::INFINITY).to_string(), "null");
+    assert_eq!(DmValue::Number(f32::NAN).to_string(), "null");
+
+    let template: Dmm = r#"
+"a" = (/obj/dmm_thing{name = "placed"; dmm_list = list("x" = 1)},/turf/turf_type_a,/area)
+"b" = (/turf/turf_type_b,/area)
+
+(1,1,1) = {"
+ab
+ba
+"}
+"#
+    .parse()?;
+    template.instantiate(ByondXYZ::with_coords((2, 2, 1)))?;
+
+    let placed_turf = byond_locatexyz(ByondXYZ::with_coords((2, 3, 1)))?;
+    assert!(placed_turf.is_type("/turf/turf_type_a")?);
+    assert!(byond_locatexyz(ByondXYZ::with_coords((3, 3, 1)))?.is_type("/turf/turf_type_b")?);
+    let contents = placed_turf.read_list("contents")?;
+    assert_eq!(contents.len(), 1);
+    assert_eq!(contents[0].read_string("name")?, "placed");
+
+    let captured = Dmm::capture(
+        ByondXYZ::with_coords((2, 2, 1)),
+        ByondXYZ::with_coords((3, 3, 1)),
+        |_, name| name == "name" || name == "dmm_list",
+    )?;
+    assert_eq!(captured.size(), (2, 2, 1));
+    let tile = captured.tile_at(ByondXYZ::with_coords((1, 2, 1))).unwrap();
+    assert_eq!(tile.prefabs.len(), 3);
+    assert_eq!(tile.prefabs[0].path, "/obj/dmm_thing");
+    assert_eq!(
+        tile.prefabs[0].var("name"),
+        Some(&DmValue::String("placed".to_owned()))
+    );
+    assert_eq!(
+        tile.prefabs[0].var("dmm_list"),
+        Some(&DmValue::List(vec![(
+            DmValue::String("x".to_owned()),
+            Some(DmValue::Number(1.0))
+        )]))
+    );
+    assert_eq!(tile.prefabs[1], Prefab::new("/turf/turf_type_a"));
+    assert_eq!(tile.prefabs[2], Prefab::new("/area"));
+
+    Ok(Default::default())
+}
@@ -4,11 +4,19 @@ pub struct Bind {
     pub proc_path: &'static str,
     pub func_name: &'static str,
     pub func_arguments: &'static str,
+    /// Arguments taken as `&mut T`, which are passed to the bind as pointers
+    pub pointer_arguments: &'static [&'static str],
     pub is_variadic: bool,
 }
 
 inventory::collect!(Bind);
 
+/// Raw DM code appended to the generated bindings, used by features that need a DM side counterpart
+/// (wrapper procs, helpers for builtins BYONDAPI doesn't expose, etc)
+pub struct DmCode(pub &'static str);
+
+inventory::collect!(DmCode);
+
 pub fn generate_bindings(libname: &str) {
     _ = std::fs::remove_file("./bindings.dm");
     let mut file = std::fs::File::create("./bindings.dm").unwrap();
@@ -50,13 +58,29 @@ pub fn generate_bindings(libname: &str) {
             ))
             .unwrap()
         } else {
+            let call_arguments = func_arguments
+                .split(',')
+                .map(str::trim)
+                .map(|arg| {
+                    if thing.pointer_arguments.contains(&arg) {
+                        format!("(ispointer({arg}) ? {arg} : &{arg})")
+                    } else {
+                        arg.to_owned()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
             file.write_fmt(format_args!(
                 r#"{path}({func_arguments_srcless})
-	return call_ext({libname_upper}, "byond:{func_name}")({func_arguments})
+	return call_ext({libname_upper}, "byond:{func_name}")({call_arguments})
 
 "#
             ))
             .unwrap()
         }
     }
+    for code in inventory::iter::<DmCode> {
+        file.write_fmt(format_args!("{}\n\n", code.0.trim()))
+            .unwrap()
+    }
 }
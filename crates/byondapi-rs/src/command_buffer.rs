@@ -0,0 +1,221 @@
+//! Recording operations on other threads and applying them all in one go on the main thread.
+//!
+//! Every [`RemoteValue`](crate::remote::RemoteValue) operation is its own
+//! [`thread_sync_blocking`](crate::threadsync::thread_sync_blocking), which adds up fast when a thread has thousands
+//! of small writes to make. A [`CommandBuffer`] records them instead, and [`CommandBuffer::apply`] runs the lot in a
+//! single sync, returning each command's result.
+//!
+//! Commands can use what earlier ones returned through the [`CommandRef`] they're recorded as:
+//! ```ignore
+//! let mut buffer = CommandBuffer::new();
+//! for (mob, damage) in hits {
+//!     buffer.write_var(mob, "health", damage);
+//! }
+//! let item = buffer.new_instance("/obj/item/gift", &[]);
+//! buffer.write_var(item, "name", "present");
+//! buffer.call(&santa, "give", &[item.into()]);
+//! let results = buffer.apply()?;
+//! ```
+use crate::{
+    main_thread::MainThread, prelude::ByondValue, remote::RemoteArg,
+    threadsync::thread_sync_deferred_with_token, Error,
+};
+
+/// The result of a recorded command, which later commands in the same buffer can use like any other argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommandRef(usize);
+
+impl CommandRef {
+    /// Where the command's result is in what [`CommandBuffer::apply`] returns
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Something to pass to a command, either a value or the result of an earlier command
+#[derive(Clone)]
+pub enum Operand {
+    Value(RemoteArg),
+    Result(CommandRef),
+}
+
+impl<T: Into<RemoteArg>> From<T> for Operand {
+    fn from(value: T) -> Self {
+        Operand::Value(value.into())
+    }
+}
+
+impl From<CommandRef> for Operand {
+    fn from(command: CommandRef) -> Self {
+        Operand::Result(command)
+    }
+}
+
+enum Command {
+    WriteVar {
+        target: Operand,
+        name: String,
+        value: Operand,
+    },
+    WriteListIndex {
+        list: Operand,
+        index: Operand,
+        value: Operand,
+    },
+    Call {
+        target: Operand,
+        name: String,
+        args: Vec<Operand>,
+    },
+    New {
+        path: Operand,
+        args: Vec<Operand>,
+    },
+}
+
+/// A list of operations to run on the main thread all at once, see the [module docs](self).
+#[derive(Default)]
+pub struct CommandBuffer {
+    commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&mut self, command: Command) -> CommandRef {
+        self.commands.push(command);
+        CommandRef(self.commands.len() - 1)
+    }
+
+    /// Records `target.name = value`, which results in null
+    pub fn write_var<T, V>(&mut self, target: T, name: &str, value: V) -> CommandRef
+    where
+        T: Into<Operand>,
+        V: Into<Operand>,
+    {
+        self.record(Command::WriteVar {
+            target: target.into(),
+            name: name.to_owned(),
+            value: value.into(),
+        })
+    }
+
+    /// Records `list[index] = value`, which results in null
+    pub fn write_list_index<L, I, V>(&mut self, list: L, index: I, value: V) -> CommandRef
+    where
+        L: Into<Operand>,
+        I: Into<Operand>,
+        V: Into<Operand>,
+    {
+        self.record(Command::WriteListIndex {
+            list: list.into(),
+            index: index.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Records a proc call on `target`, which results in what the proc returns. Like [`ByondValue::call`], this
+    /// doesn't wait for procs that sleep.
+    pub fn call<T: Into<Operand>>(
+        &mut self,
+        target: T,
+        name: &str,
+        args: &[Operand],
+    ) -> CommandRef {
+        self.record(Command::Call {
+            target: target.into(),
+            name: name.to_owned(),
+            args: args.to_vec(),
+        })
+    }
+
+    /// Records `new path(args)` through [`ByondValue::builtin_new`], which results in the new instance
+    pub fn new_instance<P: Into<Operand>>(&mut self, path: P, args: &[Operand]) -> CommandRef {
+        self.record(Command::New {
+            path: path.into(),
+            args: args.to_vec(),
+        })
+    }
+
+    /// How many commands have been recorded
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Runs every command on the main thread in one sync, blocking until they're done, and returns their results in
+    /// the order they were recorded.
+    ///
+    /// A command failing doesn't stop the rest, but anything using its result fails with
+    /// [`Error::CommandResultUnavailable`]. Results that aren't null, numbers or strings are held as
+    /// [`RemoteValue`](crate::remote::RemoteValue)s.
+    pub fn apply(self) -> Result<Vec<Result<RemoteArg, Error>>, Error> {
+        thread_sync_deferred_with_token(move |main_thread| self.apply_on(main_thread)).wait()
+    }
+
+    /// Like [`CommandBuffer::apply`], for when already on the main thread.
+    pub fn apply_on(self, _main_thread: &MainThread) -> Vec<Result<RemoteArg, Error>> {
+        let mut values: Vec<Result<ByondValue, Error>> = Vec::with_capacity(self.commands.len());
+        for command in self.commands {
+            values.push(run_command(command, &values));
+        }
+        values
+            .into_iter()
+            .map(|value| RemoteArg::from_value(value?))
+            .collect()
+    }
+}
+
+fn resolve(operand: Operand, values: &[Result<ByondValue, Error>]) -> Result<ByondValue, Error> {
+    match operand {
+        Operand::Value(value) => value.into_value(),
+        Operand::Result(CommandRef(index)) => match values.get(index) {
+            Some(Ok(value)) => Ok(*value),
+            _ => Err(Error::CommandResultUnavailable(index)),
+        },
+    }
+}
+
+fn resolve_all(
+    operands: Vec<Operand>,
+    values: &[Result<ByondValue, Error>],
+) -> Result<Vec<ByondValue>, Error> {
+    operands
+        .into_iter()
+        .map(|operand| resolve(operand, values))
+        .collect()
+}
+
+fn run_command(
+    command: Command,
+    values: &[Result<ByondValue, Error>],
+) -> Result<ByondValue, Error> {
+    match command {
+        Command::WriteVar {
+            target,
+            name,
+            value,
+        } => {
+            let mut target = resolve(target, values)?;
+            target.write_var(name, &resolve(value, values)?)?;
+            Ok(ByondValue::null())
+        }
+        Command::WriteListIndex { list, index, value } => {
+            let mut list = resolve(list, values)?;
+            list.write_list_index_internal(&resolve(index, values)?, &resolve(value, values)?)?;
+            Ok(ByondValue::null())
+        }
+        Command::Call { target, name, args } => {
+            let target = resolve(target, values)?;
+            target.call(name, &resolve_all(args, values)?)
+        }
+        Command::New { path, args } => {
+            ByondValue::builtin_new(resolve(path, values)?, &resolve_all(args, values)?)
+        }
+    }
+}
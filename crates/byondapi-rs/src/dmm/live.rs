@@ -0,0 +1,266 @@
+//! Moving maps in and out of the running world.
+//!
+//! Requires the generated `bindings.dm` to be included in your DM project.
+//!
+//! **Unlike BYOND's own map loader, var overrides are set after `New()` has run.** Anything that reads its vars in
+//! `New()` sees the defaults, so do that setup in a later step, like an `Initialize()` you call once the map is in.
+use std::collections::HashMap;
+
+use super::{DmValue, Dmm, Prefab};
+use crate::{
+    byond_string,
+    global_call::call_global_id,
+    map::{byond_block, byond_locatexyz, ByondXYZ, XYZRange},
+    prelude::*,
+    Error,
+};
+
+inventory::submit! {
+    crate::binds::DmCode(r#"
+/proc/byondapi_dmm_text2path(path)
+	return text2path(path)
+
+/proc/byondapi_dmm_file(path)
+	return file(path)
+
+/proc/byondapi_dmm_area(path)
+	var/area_type = text2path(path)
+	for(var/area/existing in world)
+		if(existing.type == area_type)
+			return existing
+	return new area_type(null)
+
+/proc/byondapi_dmm_place_area(area/target_area, turf/target)
+	target_area.contents += target
+
+/proc/byondapi_dmm_type(datum/target)
+	return "[target.type]"
+
+/proc/byondapi_dmm_changed_vars(datum/target)
+	var/static/list/skipped = list("type", "parent_type", "vars", "loc", "locs", "x", "y", "z", "contents", "verbs", "overlays", "underlays", "vis_contents", "vis_locs")
+	var/list/changed = list()
+	for(var/name in target.vars)
+		if((name in skipped) || !issaved(target.vars[name]))
+			continue
+		var/value = target.vars[name]
+		var/initial_value = initial(target.vars[name])
+		if(value == initial_value)
+			continue
+		if(islist(value) && islist(initial_value) && length(value) == length(initial_value) && !length(value ^ initial_value))
+			continue
+		changed[name] = value
+	return changed
+
+/proc/byondapi_dmm_describe(value)
+	if(ispath(value))
+		return list("path", "[value]")
+	if(isfile(value))
+		return list("resource", "[value]")
+"#)
+}
+
+/// Turns type paths into values, asking BYOND for each path only once
+#[derive(Default)]
+struct PathCache(HashMap<String, ByondValue>);
+
+impl PathCache {
+    fn get(&mut self, path: &str) -> Result<ByondValue, Error> {
+        if let Some(value) = self.0.get(path) {
+            return Ok(*value);
+        }
+        let value = call_global_id(
+            byond_string!("byondapi_dmm_text2path"),
+            &[ByondValue::new_str(path)?],
+        )?;
+        if value.is_null() {
+            return Err(Error::InvalidDmm(format!("{path} is not a type")));
+        }
+        self.0.insert(path.to_owned(), value);
+        Ok(value)
+    }
+}
+
+/// Turns a map value into a real value, or [`None`] for [`DmValue::Raw`] which can't be evaluated
+fn to_value(value: &DmValue, paths: &mut PathCache) -> Result<Option<ByondValue>, Error> {
+    Ok(Some(match value {
+        DmValue::Null => ByondValue::null(),
+        DmValue::Number(number) => ByondValue::new_num(*number),
+        DmValue::String(string) => ByondValue::new_str(string.as_str())?,
+        DmValue::Path(path) => paths.get(path)?,
+        DmValue::Resource(resource) => call_global_id(
+            byond_string!("byondapi_dmm_file"),
+            &[ByondValue::new_str(resource.as_str())?],
+        )?,
+        DmValue::List(items) => {
+            let mut list = ByondValue::new_list()?;
+            for (key, value) in items {
+                let Some(key) = to_value(key, paths)? else {
+                    continue;
+                };
+                match value {
+                    Some(value) => {
+                        if let Some(value) = to_value(value, paths)? {
+                            list.write_list_index(key, value)?;
+                        }
+                    }
+                    None => list.push_list(key)?,
+                }
+            }
+            list
+        }
+        DmValue::Raw(_) => return Ok(None),
+    }))
+}
+
+/// Turns a real value into a map value, or [`None`] if it can't be written to a map, like a datum
+fn from_value(value: ByondValue) -> Result<Option<DmValue>, Error> {
+    if value.is_null() {
+        return Ok(Some(DmValue::Null));
+    }
+    if value.is_num() {
+        let number = value.get_number()?;
+        return Ok(number.is_finite().then_some(DmValue::Number(number)));
+    }
+    if value.is_str() {
+        return Ok(Some(DmValue::String(value.get_string()?)));
+    }
+    if value.is_list() {
+        let mut items = Vec::new();
+        for (key, value) in value.iter()? {
+            let Some(key) = from_value(key)? else {
+                continue;
+            };
+            let value = if value.is_null() {
+                None
+            } else {
+                match from_value(value)? {
+                    Some(value) => Some(value),
+                    None => continue,
+                }
+            };
+            items.push((key, value));
+        }
+        return Ok(Some(DmValue::List(items)));
+    }
+    let description = call_global_id(byond_string!("byondapi_dmm_describe"), &[value])?;
+    if !description.is_list() {
+        return Ok(None);
+    }
+    let kind = description.read_list_index(1.0)?.get_string()?;
+    let text = description.read_list_index(2.0)?.get_string()?;
+    Ok(match kind.as_str() {
+        "path" => Some(DmValue::Path(text)),
+        "resource" => Some(DmValue::Resource(text)),
+        _ => None,
+    })
+}
+
+fn apply_vars(mut target: ByondValue, prefab: &Prefab, paths: &mut PathCache) -> Result<(), Error> {
+    for (name, value) in &prefab.vars {
+        if let Some(value) = to_value(value, paths)? {
+            target.write_var(name.as_str(), &value)?;
+        }
+    }
+    Ok(())
+}
+
+fn capture_prefab<F>(atom: ByondValue, filter: &mut F) -> Result<Prefab, Error>
+where
+    F: FnMut(&ByondValue, &str) -> bool,
+{
+    let path = call_global_id(byond_string!("byondapi_dmm_type"), &[atom])?.get_string()?;
+    let mut prefab = Prefab::new(path);
+    let changed = call_global_id(byond_string!("byondapi_dmm_changed_vars"), &[atom])?;
+    for (name, value) in changed.iter()? {
+        let name = name.get_string()?;
+        if !filter(&atom, &name) {
+            continue;
+        }
+        if let Some(value) = from_value(value)? {
+            prefab.vars.push((name, value));
+        }
+    }
+    Ok(prefab)
+}
+
+impl Dmm {
+    /// Creates this map in the world, with the map's `(1, 1, 1)` at `origin`.
+    ///
+    /// Turfs are replaced and added to the first area in the world of exactly the map's type, or a new one,
+    /// and movables are created on top. Areas keep their own vars.
+    /// [`DmValue::Raw`] vars are skipped, and so are tiles that land outside the world.
+    ///
+    /// # `New()` sees default vars
+    /// Var overrides are set right after each atom is created, not before `New()` like BYOND's map loader does.
+    /// Types whose `New()` reads vars the map overrides won't act on the map's values, see the
+    /// [module docs](self).
+    pub fn instantiate(&self, origin: ByondXYZ) -> Result<(), Error> {
+        let mut paths = PathCache::default();
+        let mut areas = HashMap::new();
+        for (xyz, tile) in self.iter() {
+            let target = origin + (xyz.x() - 1, xyz.y() - 1, xyz.z() - 1);
+            let mut turf = byond_locatexyz(target)?;
+            if turf.is_null() {
+                continue;
+            }
+
+            for prefab in tile.prefabs.iter().filter(|prefab| prefab.is_turf()) {
+                turf = ByondValue::builtin_new(paths.get(&prefab.path)?, &[turf])?;
+                apply_vars(turf, prefab, &mut paths)?;
+            }
+            for prefab in tile.prefabs.iter().filter(|prefab| prefab.is_area()) {
+                let area = match areas.get(&prefab.path) {
+                    Some(area) => *area,
+                    None => {
+                        let area = call_global_id(
+                            byond_string!("byondapi_dmm_area"),
+                            &[ByondValue::new_str(prefab.path.as_str())?],
+                        )?;
+                        areas.insert(prefab.path.clone(), area);
+                        area
+                    }
+                };
+                call_global_id(byond_string!("byondapi_dmm_place_area"), &[area, turf])?;
+            }
+            for prefab in tile
+                .prefabs
+                .iter()
+                .filter(|prefab| !prefab.is_turf() && !prefab.is_area())
+            {
+                let movable = ByondValue::builtin_new(paths.get(&prefab.path)?, &[turf])?;
+                apply_vars(movable, prefab, &mut paths)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Captures the cuboid of the world between two corners into a map, with the lowest corner at `(1, 1, 1)`.
+    ///
+    /// Every atom on each turf is captured, along with the turf and its area. Vars are kept if they can be saved,
+    /// differ from their defaults and `filter` returns true for the atom and var name.
+    /// Vars holding things that can't go in a map, like datums or numbers that aren't finite, are dropped.
+    pub fn capture<F>(corner1: ByondXYZ, corner2: ByondXYZ, mut filter: F) -> Result<Self, Error>
+    where
+        F: FnMut(&ByondValue, &str) -> bool,
+    {
+        let range = XYZRange::new(corner1, corner2);
+        let min = range.min_corner();
+        let (dx, dy, dz) = range.max_corner() - min;
+        let mut dmm = Dmm::new((dx + 1, dy + 1, dz + 1), Vec::new());
+
+        let turfs = byond_block(corner1, corner2)?;
+        for (xyz, turf) in range.zip(turfs) {
+            let mut prefabs = Vec::new();
+            for atom in turf.read_list_id(byond_string!("contents"))? {
+                prefabs.push(capture_prefab(atom, &mut filter)?);
+            }
+            prefabs.push(capture_prefab(turf, &mut filter)?);
+            let area = turf.read_var_id(byond_string!("loc"))?;
+            prefabs.push(capture_prefab(area, &mut filter)?);
+
+            let (x, y, z) = xyz - min;
+            dmm.set_prefabs(ByondXYZ::with_coords((x + 1, y + 1, z + 1)), prefabs);
+        }
+        Ok(dmm)
+    }
+}
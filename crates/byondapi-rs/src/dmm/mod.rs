@@ -0,0 +1,170 @@
+//! Reading and writing `.dmm` map files, in both DreamMaker's classic format and the TGM format.
+//!
+//! A [`Dmm`] parses from text with [`str::parse`] and writes back out with [`ToString`], in whichever
+//! format it was read as. [`Dmm::instantiate`] and [`Dmm::capture`] move maps in and out of the running world,
+//! though `instantiate` sets var overrides after `New()` rather than before it, see [`live`].
+//! ```ignore
+//! let map: Dmm = std::fs::read_to_string("maps/shuttle.dmm")?.parse()?;
+//! map.instantiate(ByondXYZ::with_coords((50, 50, 2)))?;
+//! ```
+use crate::map::{ByondXYZ, XYZRange};
+
+pub mod live;
+mod parse;
+mod write;
+
+/// A value assigned to a var in a map file
+#[derive(Debug, Clone, PartialEq)]
+pub enum DmValue {
+    Null,
+    /// Written as `null` if it isn't finite, since maps can't hold NaN or infinity
+    Number(f32),
+    String(String),
+    /// A type path, like `/obj/item`
+    Path(String),
+    /// A file, like `'icons/obj/items.dmi'`, without the quotes
+    Resource(String),
+    /// A `list(...)`, with the associated value of each item if it has one
+    List(Vec<(DmValue, Option<DmValue>)>),
+    /// Anything else, like `newlist(...)` or `matrix(...)`, kept exactly as it was written
+    Raw(String),
+}
+
+/// An instance of a type path with some vars changed from their defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prefab {
+    pub path: String,
+    pub vars: Vec<(String, DmValue)>,
+}
+
+impl Prefab {
+    pub fn new<T: Into<String>>(path: T) -> Self {
+        Self {
+            path: path.into(),
+            vars: Vec::new(),
+        }
+    }
+
+    /// Adds a var override
+    pub fn with_var<T: Into<String>>(mut self, name: T, value: DmValue) -> Self {
+        self.vars.push((name.into(), value));
+        self
+    }
+
+    /// Gets the value a var is overridden with, if it is
+    pub fn var(&self, name: &str) -> Option<&DmValue> {
+        self.vars
+            .iter()
+            .find(|(var_name, _)| var_name == name)
+            .map(|(_, value)| value)
+    }
+
+    fn is_turf(&self) -> bool {
+        self.path == "/turf" || self.path.starts_with("/turf/")
+    }
+
+    fn is_area(&self) -> bool {
+        self.path == "/area" || self.path.starts_with("/area/")
+    }
+}
+
+/// One entry in a map's dictionary, everything on a tile
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tile {
+    /// The key used for this tile in the file. Keys are regenerated on write if they're missing or inconsistent.
+    pub key: String,
+    /// Movables first, then the turf, then the area
+    pub prefabs: Vec<Prefab>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DmmFormat {
+    /// What DreamMaker writes, one key per tile and one block per z-level
+    #[default]
+    Classic,
+    /// Written with one prefab and one var per line and one block per column, to merge better
+    Tgm,
+}
+
+/// A parsed map, made of a dictionary of [`Tile`]s and a grid saying which of them goes where.
+///
+/// Coordinates are relative to the map, starting at `(1, 1, 1)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dmm {
+    pub format: DmmFormat,
+    pub tiles: Vec<Tile>,
+    size: (i16, i16, i16),
+    /// Index into `tiles` for every coordinate, in block order
+    grid: Vec<usize>,
+}
+
+impl Dmm {
+    /// Creates a map of this size with every tile holding `prefabs`
+    pub fn new(size: (i16, i16, i16), prefabs: Vec<Prefab>) -> Self {
+        let (x, y, z) = size;
+        let area = x.max(0) as usize * y.max(0) as usize * z.max(0) as usize;
+        Self {
+            format: DmmFormat::default(),
+            tiles: vec![Tile {
+                key: String::new(),
+                prefabs,
+            }],
+            size,
+            grid: vec![0; area],
+        }
+    }
+
+    /// The size of the map as `(x, y, z)`
+    pub fn size(&self) -> (i16, i16, i16) {
+        self.size
+    }
+
+    fn index(&self, xyz: ByondXYZ) -> Option<usize> {
+        let (maxx, maxy, maxz) = self.size;
+        let (x, y, z) = xyz.coordinates();
+        if x < 1 || y < 1 || z < 1 || x > maxx || y > maxy || z > maxz {
+            return None;
+        }
+        let (width, height) = (maxx as usize, maxy as usize);
+        Some((x - 1) as usize + (y - 1) as usize * width + (z - 1) as usize * width * height)
+    }
+
+    /// Gets the tile at these coordinates, or [`None`] if they're outside the map
+    pub fn tile_at(&self, xyz: ByondXYZ) -> Option<&Tile> {
+        self.tiles.get(self.grid[self.index(xyz)?])
+    }
+
+    /// Replaces everything on the tile at these coordinates, reusing a matching dictionary entry if there is one.
+    /// Does nothing if they're outside the map.
+    pub fn set_prefabs(&mut self, xyz: ByondXYZ, prefabs: Vec<Prefab>) {
+        let Some(index) = self.index(xyz) else {
+            return;
+        };
+        let tile = match self.tiles.iter().position(|tile| tile.prefabs == prefabs) {
+            Some(tile) => tile,
+            None => {
+                self.tiles.push(Tile {
+                    key: String::new(),
+                    prefabs,
+                });
+                self.tiles.len() - 1
+            }
+        };
+        self.grid[index] = tile;
+    }
+
+    /// Iterates over every coordinate in the map and its tile, in block order
+    pub fn iter(&self) -> impl Iterator<Item = (ByondXYZ, &Tile)> {
+        let range = (!self.grid.is_empty()).then(|| {
+            XYZRange::new(
+                ByondXYZ::with_coords((1, 1, 1)),
+                ByondXYZ::with_coords(self.size),
+            )
+        });
+        range
+            .into_iter()
+            .flatten()
+            .zip(&self.grid)
+            .map(|(xyz, tile)| (xyz, &self.tiles[*tile]))
+    }
+}
@@ -0,0 +1,360 @@
+use std::{collections::HashMap, str::FromStr};
+
+use super::{DmValue, Dmm, DmmFormat, Prefab, Tile};
+use crate::Error;
+
+/// The header `dmm2tgm.py` and compatible tools put at the top of TGM maps
+pub(super) const TGM_HEADER: &str =
+    "//MAP CONVERTED BY dmm2tgm.py THIS HEADER COMMENT PREVENTS RECONVERSION, DO NOT REMOVE";
+
+impl FromStr for Dmm {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Parser { text, pos: 0 }.parse()
+    }
+}
+
+/// Whether a backslash followed by this is left alone in strings, rather than being an escape we understand
+pub(super) fn is_kept_escape(c: char) -> bool {
+    (c.is_ascii_alphabetic() && c != 'n') || c == '[' || c == ']'
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, message: &str) -> Result<T, Error> {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        Err(Error::InvalidDmm(format!("line {line}: {message}")))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => self.error(&format!("expected '{expected}'")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    /// Skips whitespace and `//` comments, which are only allowed between entries
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            self.skip_whitespace();
+            if !self.text[self.pos..].starts_with("//") {
+                return;
+            }
+            match self.text[self.pos..].find('\n') {
+                Some(end) => self.pos += end,
+                None => self.pos = self.text.len(),
+            }
+        }
+    }
+
+    /// Takes characters while `f` holds for them
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> &str {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.next();
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn parse(mut self) -> Result<Dmm, Error> {
+        let format = if self.text.trim_start().starts_with(TGM_HEADER) {
+            DmmFormat::Tgm
+        } else {
+            DmmFormat::Classic
+        };
+
+        let mut tiles = Vec::new();
+        let mut keys = HashMap::new();
+        let mut placed = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments();
+            match self.peek() {
+                None => break,
+                Some('"') => {
+                    let tile = self.parse_tile()?;
+                    if keys.insert(tile.key.clone(), tiles.len()).is_some() {
+                        return self.error(&format!("key \"{}\" is defined twice", tile.key));
+                    }
+                    tiles.push(tile);
+                }
+                Some('(') => self.parse_block(&keys, &mut placed)?,
+                Some(_) => return self.error("expected a dictionary entry or a block of tiles"),
+            }
+        }
+
+        let size = placed.iter().fold((0, 0, 0), |size, &((x, y, z), _)| {
+            (size.0.max(x), size.1.max(y), size.2.max(z))
+        });
+        let mut grid = vec![usize::MAX; size.0 as usize * size.1 as usize * size.2 as usize];
+        let (width, height) = (size.0 as usize, size.1 as usize);
+        for ((x, y, z), tile) in placed {
+            let index =
+                (x - 1) as usize + (y - 1) as usize * width + (z - 1) as usize * width * height;
+            grid[index] = tile;
+        }
+        if grid.contains(&usize::MAX) {
+            return Err(Error::InvalidDmm("some tiles have no key".to_owned()));
+        }
+
+        Ok(Dmm {
+            format,
+            tiles,
+            size,
+            grid,
+        })
+    }
+
+    /// `"key" = (/path{var = value},/path)`
+    fn parse_tile(&mut self) -> Result<Tile, Error> {
+        self.expect('"')?;
+        let key = self.take_while(|c| c != '"').to_owned();
+        self.expect('"')?;
+        self.expect('=')?;
+        self.expect('(')?;
+        let mut prefabs = Vec::new();
+        loop {
+            self.skip_whitespace();
+            prefabs.push(self.parse_prefab()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(')') => break,
+                _ => return self.error("expected ',' or ')' after a prefab"),
+            }
+        }
+        Ok(Tile { key, prefabs })
+    }
+
+    fn parse_prefab(&mut self) -> Result<Prefab, Error> {
+        let path = self
+            .take_while(|c| !c.is_whitespace() && !matches!(c, ',' | ')' | '{'))
+            .to_owned();
+        if !path.starts_with('/') {
+            return self.error("expected a type path");
+        }
+        let mut prefab = Prefab::new(path);
+
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return Ok(prefab);
+        }
+        self.next();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                self.next();
+                break;
+            }
+            let name = self
+                .take_while(|c| c.is_alphanumeric() || c == '_')
+                .to_owned();
+            if name.is_empty() {
+                return self.error("expected a var name");
+            }
+            self.expect('=')?;
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            prefab.vars.push((name, value));
+            self.skip_whitespace();
+            match self.next() {
+                Some(';') => continue,
+                Some('}') => break,
+                _ => return self.error("expected ';' or '}' after a var"),
+            }
+        }
+        Ok(prefab)
+    }
+
+    fn parse_value(&mut self) -> Result<DmValue, Error> {
+        match self.peek() {
+            Some('"') => self.parse_string().map(DmValue::String),
+            Some('\'') => {
+                self.next();
+                let resource = self.take_while(|c| c != '\'').to_owned();
+                self.expect('\'')?;
+                Ok(DmValue::Resource(resource))
+            }
+            Some('/') => {
+                let path = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '/');
+                Ok(DmValue::Path(path.to_owned()))
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let start = self.pos;
+                let number = self.take_while(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+                match number.parse() {
+                    Ok(number) => Ok(DmValue::Number(number)),
+                    Err(_) => {
+                        self.pos = start;
+                        self.parse_raw()
+                    }
+                }
+            }
+            _ if self.text[self.pos..].starts_with("list(") => {
+                self.pos += "list(".len();
+                self.parse_list()
+            }
+            _ => match self.parse_raw()? {
+                DmValue::Raw(raw) if raw == "null" => Ok(DmValue::Null),
+                raw => Ok(raw),
+            },
+        }
+    }
+
+    /// A string, with escapes unescaped except for text macros like `\improper` and escaped brackets,
+    /// which are kept as they are.
+    fn parse_string(&mut self) -> Result<String, Error> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('n') => string.push('\n'),
+                    Some(c) if is_kept_escape(c) => {
+                        string.push('\\');
+                        string.push(c);
+                    }
+                    Some(c) => string.push(c),
+                    None => break,
+                },
+                Some(c) => string.push(c),
+                None => break,
+            }
+        }
+        self.error("unterminated string")
+    }
+
+    /// The rest of a `list(...)`, after the opening bracket
+    fn parse_list(&mut self) -> Result<DmValue, Error> {
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(')') {
+            self.next();
+            return Ok(DmValue::List(items));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_value()?;
+            self.skip_whitespace();
+            let value = if self.peek() == Some('=') {
+                self.next();
+                self.skip_whitespace();
+                let value = self.parse_value()?;
+                self.skip_whitespace();
+                Some(value)
+            } else {
+                None
+            };
+            items.push((key, value));
+            match self.next() {
+                Some(',') => continue,
+                Some(')') => return Ok(DmValue::List(items)),
+                _ => return self.error("expected ',' or ')' in a list"),
+            }
+        }
+    }
+
+    /// Anything we don't understand, up to the end of the var or list item it's in
+    fn parse_raw(&mut self) -> Result<DmValue, Error> {
+        let start = self.pos;
+        let mut depth = 0;
+        let mut in_string = false;
+        while let Some(c) = self.peek() {
+            if in_string {
+                match c {
+                    '\\' => {
+                        self.next();
+                    }
+                    '"' => in_string = false,
+                    _ => {}
+                }
+            } else {
+                match c {
+                    '"' => in_string = true,
+                    '(' | '{' => depth += 1,
+                    ')' | '}' if depth > 0 => depth -= 1,
+                    ';' | '}' | ',' | ')' | '=' if depth == 0 => break,
+                    _ => {}
+                }
+            }
+            self.next();
+        }
+        let raw = self.text[start..self.pos].trim();
+        if raw.is_empty() {
+            return self.error("expected a value");
+        }
+        Ok(DmValue::Raw(raw.to_owned()))
+    }
+
+    /// `(x,y,z) = {"keys"}`, placing the keys into `placed` by their coordinates
+    fn parse_block(
+        &mut self,
+        keys: &HashMap<String, usize>,
+        placed: &mut Vec<((i16, i16, i16), usize)>,
+    ) -> Result<(), Error> {
+        self.expect('(')?;
+        let mut origin = [0i16; 3];
+        for (i, coordinate) in origin.iter_mut().enumerate() {
+            if i > 0 {
+                self.expect(',')?;
+            }
+            self.skip_whitespace();
+            *coordinate = match self.take_while(|c| c.is_ascii_digit()).parse() {
+                Ok(coordinate) if coordinate >= 1 => coordinate,
+                _ => return self.error("expected a coordinate"),
+            };
+        }
+        self.expect(')')?;
+        self.expect('=')?;
+        self.expect('{')?;
+        self.expect('"')?;
+        let Some(end) = self.text[self.pos..].find("\"}") else {
+            return self.error("unterminated block of tiles");
+        };
+        let body = &self.text[self.pos..self.pos + end];
+
+        let key_length = keys.keys().next().map_or(1, |key| key.chars().count());
+        let rows: Vec<&str> = body
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+        let [x, y, z] = origin;
+        for (row_number, row) in rows.iter().enumerate() {
+            // The first row is the top of the block
+            let row_y = y + (rows.len() - row_number - 1) as i16;
+            let chars: Vec<char> = row.chars().collect();
+            for (column, key) in chars.chunks(key_length).enumerate() {
+                let key: String = key.iter().collect();
+                let Some(&tile) = keys.get(&key) else {
+                    return self.error(&format!("key \"{key}\" isn't in the dictionary"));
+                };
+                placed.push(((x + column as i16, row_y, z), tile));
+            }
+        }
+        self.pos += end + "\"}".len();
+        Ok(())
+    }
+}
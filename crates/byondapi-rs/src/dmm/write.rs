@@ -0,0 +1,172 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter, Write},
+};
+
+use super::{
+    parse::{is_kept_escape, TGM_HEADER},
+    DmValue, Dmm, DmmFormat, Prefab,
+};
+
+const KEY_CHARACTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Makes `count` unique keys, as short as they can be
+fn generate_keys(count: usize) -> Vec<String> {
+    let base = KEY_CHARACTERS.len();
+    let mut length = 1;
+    while base.pow(length as u32) < count {
+        length += 1;
+    }
+    (0..count)
+        .map(|mut index| {
+            let mut key = vec![0; length];
+            for character in key.iter_mut().rev() {
+                *character = KEY_CHARACTERS[index % base];
+                index /= base;
+            }
+            String::from_utf8(key).unwrap()
+        })
+        .collect()
+}
+
+impl Display for DmValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DmValue::Null => f.write_str("null"),
+            // DM has no literal for these
+            DmValue::Number(number) if !number.is_finite() => f.write_str("null"),
+            DmValue::Number(number) => write!(f, "{number}"),
+            DmValue::String(string) => {
+                f.write_char('"')?;
+                let mut chars = string.chars().peekable();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\n' => f.write_str("\\n")?,
+                        // Text macros and escaped brackets were kept as they were, any other backslash is escaped
+                        '\\' if !chars.peek().is_some_and(|&c| is_kept_escape(c)) => {
+                            f.write_str("\\\\")?
+                        }
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            DmValue::Path(path) => f.write_str(path),
+            DmValue::Resource(resource) => write!(f, "'{resource}'"),
+            DmValue::List(items) => {
+                f.write_str("list(")?;
+                for (i, (key, value)) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    match value {
+                        Some(value) => write!(f, "{key} = {value}")?,
+                        None => write!(f, "{key}")?,
+                    }
+                }
+                f.write_char(')')
+            }
+            DmValue::Raw(raw) => f.write_str(raw),
+        }
+    }
+}
+
+fn write_prefab(f: &mut Formatter<'_>, prefab: &Prefab, format: DmmFormat) -> fmt::Result {
+    f.write_str(&prefab.path)?;
+    if prefab.vars.is_empty() {
+        return Ok(());
+    }
+    let (open, separator, close) = match format {
+        DmmFormat::Classic => ("{", "; ", "}"),
+        DmmFormat::Tgm => ("{\n\t", ";\n\t", "\n\t}"),
+    };
+    f.write_str(open)?;
+    for (i, (name, value)) in prefab.vars.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        write!(f, "{name} = {value}")?;
+    }
+    f.write_str(close)
+}
+
+impl Display for Dmm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut used = vec![false; self.tiles.len()];
+        for &tile in &self.grid {
+            used[tile] = true;
+        }
+        let tiles: Vec<usize> = (0..self.tiles.len()).filter(|&tile| used[tile]).collect();
+
+        let key_length = tiles.first().map_or(0, |&tile| self.tiles[tile].key.len());
+        let mut seen = HashSet::new();
+        let keys_valid = tiles.iter().all(|&tile| {
+            let key = &self.tiles[tile].key;
+            key_length > 0
+                && key.len() == key_length
+                && key.bytes().all(|c| c.is_ascii_alphabetic())
+                && seen.insert(key)
+        });
+        let mut keys = vec![String::new(); self.tiles.len()];
+        if keys_valid {
+            for &tile in &tiles {
+                keys[tile] = self.tiles[tile].key.clone();
+            }
+        } else {
+            for (&tile, key) in tiles.iter().zip(generate_keys(tiles.len())) {
+                keys[tile] = key;
+            }
+        }
+
+        if self.format == DmmFormat::Tgm {
+            writeln!(f, "{TGM_HEADER}")?;
+        }
+        for &tile in &tiles {
+            write!(f, "\"{}\" = (", keys[tile])?;
+            for (i, prefab) in self.tiles[tile].prefabs.iter().enumerate() {
+                match (self.format, i) {
+                    (DmmFormat::Classic, 0) => {}
+                    (DmmFormat::Classic, _) => f.write_char(',')?,
+                    (DmmFormat::Tgm, 0) => f.write_char('\n')?,
+                    (DmmFormat::Tgm, _) => f.write_str(",\n")?,
+                }
+                write_prefab(f, prefab, self.format)?;
+            }
+            writeln!(f, ")")?;
+        }
+        writeln!(f)?;
+
+        let (maxx, maxy, maxz) = self.size;
+        let (width, height) = (maxx as usize, maxy as usize);
+        let key_at = |x: i16, y: i16, z: i16| {
+            let index =
+                (x - 1) as usize + (y - 1) as usize * width + (z - 1) as usize * width * height;
+            &keys[self.grid[index]]
+        };
+        for z in 1..=maxz {
+            match self.format {
+                DmmFormat::Classic => {
+                    writeln!(f, "(1,1,{z}) = {{\"")?;
+                    for y in (1..=maxy).rev() {
+                        for x in 1..=maxx {
+                            f.write_str(key_at(x, y, z))?;
+                        }
+                        writeln!(f)?;
+                    }
+                    writeln!(f, "\"}}")?;
+                }
+                DmmFormat::Tgm => {
+                    for x in 1..=maxx {
+                        writeln!(f, "({x},1,{z}) = {{\"")?;
+                        for y in (1..=maxy).rev() {
+                            writeln!(f, "{}", key_at(x, y, z))?;
+                        }
+                        writeln!(f, "\"}}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
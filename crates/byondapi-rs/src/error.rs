@@ -1,12 +1,24 @@
 //! Error types for any problems this runs into, including internal BYOND errors.
-use std::ffi::{CStr, CString};
+use std::{
+    backtrace::Backtrace,
+    ffi::{CStr, CString},
+};
 
-use crate::{prelude::ByondValue, static_global::byond};
+use crate::{
+    prelude::{ByondValue, ValueType},
+    static_global::byond,
+    sys::u4c,
+};
 
 #[derive(Debug)]
 pub enum Error {
     /// This error is thrown when you try to convert a [`crate::ByondValue`] into a type which it does not represent, or the value failed to convert to a [`crate::ByondValue`].
-    InvalidConversion,
+    InvalidConversion {
+        /// The type that was being converted to
+        expected: &'static str,
+        /// The type, or the value, that was being converted from
+        actual: String,
+    },
     /// This error is thrown from call when you try to call something that isn't in BYOND's string tree (thus is not a valid proc)
     InvalidProc(CString),
     /// Thrown when trying to get a [`String`] from a [`crate::ByondValue`].
@@ -32,6 +44,31 @@ pub enum Error {
     NonExistentString(CString),
     /// Thrown when we know byondland failed to create a string
     UnableToCreateString(CString),
+    /// Thrown by us when we know this value is not of the type path we're expecting
+    NotOfType(ByondValue, String),
+    /// Thrown when a proc called through [`crate::sleeping_call`] runtimed, contains the name of the exception
+    ProcRuntime(String),
+    /// Thrown by [`crate::dmm`] when a map file can't be parsed or refers to types that don't exist
+    InvalidDmm(String),
+    /// Thrown by [`crate::threadsync`] when the callback panicked, contains the panic message
+    CallbackPanicked(String),
+    /// Thrown by [`crate::jobs`] when a job was cancelled
+    JobCancelled,
+    /// Thrown by [`crate::jobs`] when a job ran past its time limit
+    JobTimedOut,
+    /// Thrown by [`crate::jobs`] when a job returned an error, contains the error's debug representation
+    JobFailed(String),
+    /// Thrown by [`crate::command_buffer`] when a command uses the result of one that failed or hasn't run yet,
+    /// contains that command's index
+    CommandResultUnavailable(usize),
+    /// Thrown by [`crate::map`] when `block()` gave back a different number of turfs than the region has
+    UnexpectedBlockSize { expected: usize, actual: usize },
+    /// Another error, along with what was being done when it happened. The error itself is its
+    /// [`source`](std::error::Error::source), match on [`Error::root_cause`] to get at it.
+    WithContext {
+        context: Box<ErrorContext>,
+        source: Box<Error>,
+    },
 }
 
 impl Error {
@@ -42,16 +79,152 @@ impl Error {
             Self::UnknownByondError
         }
     }
+
+    /// An [`Error::InvalidConversion`] for when `value` couldn't be turned into a `T`
+    pub fn conversion_from<T>(value: &ByondValue) -> Self {
+        Self::InvalidConversion {
+            expected: std::any::type_name::<T>(),
+            actual: type_of(value),
+        }
+    }
+
+    /// An [`Error::InvalidConversion`] for when a `T` couldn't be turned into a [`ByondValue`]
+    pub fn conversion_into<T>() -> Self {
+        Self::InvalidConversion {
+            expected: "ByondValue",
+            actual: std::any::type_name::<T>().to_owned(),
+        }
+    }
+
+    /// Wraps this in an [`Error::WithContext`]
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        Self::WithContext {
+            context: Box::new(context),
+            source: Box::new(self),
+        }
+    }
+
+    /// The innermost context this error has, which is the closest to where it happened
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::WithContext { context, source } => source.context().or(Some(context)),
+            _ => None,
+        }
+    }
+
+    /// The error underneath any context, for matching on what actually went wrong
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Self::WithContext { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+
+    /// Where the innermost context was added, if backtraces were enabled then
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.context().map(|context| &context.backtrace)
+    }
+}
+
+/// Reports an error that has nowhere else to go through DM's `stack_trace`, for things running outside of a bind
+pub(crate) fn report_error(error: Error) {
+    if let Ok(message) = ByondValue::new_str(format!("{error:?}")) {
+        let _ = crate::global_call::call_global_id(crate::byond_string!("stack_trace"), &[message]);
+    }
+}
+
+/// A value's type without calling into BYOND, for error messages
+fn type_of(value: &ByondValue) -> String {
+    match ValueType::try_from(value.0.type_) {
+        Ok(value_type) => format!("{value_type:?}"),
+        Err(_) => format!("Unknown({:X})", value.0.type_),
+    }
+}
+
+/// What was being done when an error happened, see [`Error::WithContext`]
+#[derive(Debug)]
+pub struct ErrorContext {
+    /// Like `read_var`, `call` or `read_list_index`
+    pub operation: &'static str,
+    /// The var or proc name, list index, or whatever else the operation was given
+    pub name: Option<String>,
+    /// The value the operation was done to
+    pub target: Option<ByondValue>,
+    /// Only captured if `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` is set, see [`Backtrace::capture`]
+    pub backtrace: Backtrace,
+}
+
+impl ErrorContext {
+    pub fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            name: None,
+            target: None,
+            backtrace: Backtrace::capture(),
+        }
+    }
+
+    pub fn name<T: Into<String>>(mut self, name: T) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn target(mut self, target: ByondValue) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Uses a value as the name, for things like list indexes. Strings and numbers are shown as they are. Must be
+    /// called on the main thread.
+    pub fn name_value(self, value: &ByondValue) -> Self {
+        let name = if value.is_str() {
+            value
+                .get_string()
+                .map_or_else(|_| type_of(value), |string| format!("{string:?}"))
+        } else if value.is_num() {
+            value
+                .get_number()
+                .map_or_else(|_| type_of(value), |number| number.to_string())
+        } else {
+            format!("{value:?}")
+        };
+        self.name(name)
+    }
+
+    /// Uses the string with this id as the name, for things looked up by string id. Must be called on the main
+    /// thread.
+    pub fn name_id(self, id: u4c) -> Self {
+        let name = ByondValue::new_ref(ValueType::String, id)
+            .get_string()
+            .unwrap_or_else(|_| format!("string id {id}"));
+        self.name(name)
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.operation)?;
+        if let Some(name) = &self.name {
+            write!(f, " {name}")?;
+        }
+        if let Some(target) = &self.target {
+            write!(f, " on {target:?}")?;
+        }
+        Ok(())
+    }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::InvalidConversion => write!(f, "Cannot convert value to target type"),
+            Self::InvalidConversion { expected, actual } => {
+                write!(f, "Cannot convert {actual} to {expected}")
+            }
             Self::InvalidProc(procname) => {
                 write!(f, "Cannot call proc {procname:?}, proc doesn't exist")
             }
             Self::NonUtf8String => write!(f, "String is not utf8"),
+            Self::ByondError(e) if e.0.is_empty() => write!(f, "Byondapi error with no message"),
             Self::ByondError(e) => write!(f, "Byondapi error: {:#?}", e.0),
             Self::UnknownByondError => write!(f, "Unknown byondapi error"),
             Self::NotAvailableForThisByondVersion => write!(
@@ -67,11 +240,39 @@ impl std::fmt::Display for Error {
             Self::UnableToCreateString(string) => {
                 write!(f, "Unable to create string \"{string:#?}\"")
             }
+            Self::NotOfType(val, path) => write!(f, "Value is not a {path} {val:?}"),
+            Self::ProcRuntime(error) => write!(f, "Proc runtimed: {error}"),
+            Self::InvalidDmm(error) => write!(f, "Invalid map: {error}"),
+            Self::CallbackPanicked(message) => write!(f, "Callback panicked: {message}"),
+            Self::JobCancelled => write!(f, "Job was cancelled"),
+            Self::JobTimedOut => write!(f, "Job ran past its time limit"),
+            Self::JobFailed(error) => write!(f, "Job failed: {error}"),
+            Self::CommandResultUnavailable(index) => {
+                write!(f, "Result of command {index} is unavailable")
+            }
+            Self::UnexpectedBlockSize { expected, actual } => {
+                write!(f, "Expected {expected} turfs from block, got {actual}")
+            }
+            Self::WithContext { context, source } => write!(f, "{context} failed: {source}"),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::WithContext { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Lets infallible conversions, like into [`ByondValue`], be used where fallible ones are expected
+impl From<std::convert::Infallible> for Error {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
 
 #[derive(Debug)]
 pub struct ByondError(pub CString);
@@ -1,6 +1,6 @@
 use crate::prelude::*;
 use crate::static_global::byond;
-use crate::Error;
+use crate::{error::ErrorContext, Error};
 
 use std::ffi::CString;
 
@@ -10,10 +10,12 @@ use std::ffi::CString;
 pub fn call_global<T: Into<Vec<u8>>>(name: T, args: &[ByondValue]) -> Result<ByondValue, Error> {
     let c_string = CString::new(name).unwrap();
     let c_str = c_string.as_c_str();
+    let context =
+        |e: Error| e.with_context(ErrorContext::new("call_global").name(c_str.to_string_lossy()));
 
     let str_id = unsafe { byond().Byond_GetStrId(c_str.as_ptr()) };
     if str_id == crate::sys::u2c::MAX as u32 {
-        return Err(Error::InvalidProc(c_string));
+        return Err(context(Error::InvalidProc(c_string.clone())));
     }
     let ptr = args.as_ptr();
     let mut new_value = ByondValue::new();
@@ -23,7 +25,8 @@ pub fn call_global<T: Into<Vec<u8>>>(name: T, args: &[ByondValue]) -> Result<Byo
             ptr.cast(),
             args.len() as u32,
             &mut new_value.0
-        ))?;
+        ))
+        .map_err(context)?;
     }
     Ok(new_value)
 }
@@ -40,7 +43,8 @@ pub fn call_global_id(name: u4c, args: &[ByondValue]) -> Result<ByondValue, Erro
             ptr.cast(),
             args.len() as u32,
             &mut new_value.0
-        ))?;
+        ))
+        .map_err(|e| e.with_context(ErrorContext::new("call_global").name_id(name)))?;
     }
     Ok(new_value)
 }
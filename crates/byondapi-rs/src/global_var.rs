@@ -0,0 +1,55 @@
+//! Reading and writing DM `global.` variables.
+use std::sync::OnceLock;
+
+use crate::prelude::*;
+use crate::{global_call::call_global_id, Error};
+
+inventory::submit! {
+    crate::binds::DmCode(r#"
+/proc/byondapi_global_vars()
+	return global.vars
+"#)
+}
+
+/// Gets DM's `global.vars` list, a [`ValueType::GlobalVars`] list of every global var keyed by name.
+pub fn globals() -> Result<ByondValue, Error> {
+    // global.vars is a special list which never goes away, so we only have to fetch it once
+    static GLOBALS: OnceLock<ByondValue> = OnceLock::new();
+    if let Some(globals) = GLOBALS.get() {
+        return Ok(*globals);
+    }
+    let globals = call_global_id(byond_string!("byondapi_global_vars"), &[])?;
+    if !globals.is_list() {
+        return Err(Error::NotAList(globals));
+    }
+    Ok(*GLOBALS.get_or_init(|| globals))
+}
+
+/// Reads a global variable by name.
+pub fn read_global<T: Into<Vec<u8>>>(name: T) -> Result<ByondValue, Error> {
+    globals()?.read_list_index_internal(&ByondValue::new_str(name)?)
+}
+
+/// Reads a global variable by the string id of its name.
+pub fn read_global_id(name: u4c) -> Result<ByondValue, Error> {
+    globals()?.read_list_index_internal(&ByondValue::new_ref(ValueType::String, name))
+}
+
+/// Writes to a global variable by name.
+pub fn write_global<T: Into<Vec<u8>>>(name: T, value: &ByondValue) -> Result<(), Error> {
+    globals()?.write_list_index_internal(&ByondValue::new_str(name)?, value)
+}
+
+/// Writes to a global variable by the string id of its name.
+pub fn write_global_id(name: u4c, value: &ByondValue) -> Result<(), Error> {
+    globals()?.write_list_index_internal(&ByondValue::new_ref(ValueType::String, name), value)
+}
+
+/// Gets the names of every global variable.
+pub fn global_var_names() -> Result<Vec<String>, Error> {
+    globals()?
+        .get_list_values()?
+        .iter()
+        .map(ByondValue::get_string)
+        .collect()
+}
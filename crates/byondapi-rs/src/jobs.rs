@@ -0,0 +1,390 @@
+//! Running long work on a pool of worker threads, so it doesn't stall ticks.
+//!
+//! [`spawn_job`] queues a closure and returns a [`JobId`] straight away, which DM can check on with the
+//! generated procs:
+//! - `byondapi_job_status(id)`: `"queued"`, `"running"`, `"done"`, `"failed"`, `"cancelled"`, `"timed out"`,
+//!   or `"unknown"` for ids that don't exist or have already been collected or evicted
+//! - `byondapi_job_done(id)`: whether the job has stopped for any reason
+//! - `byondapi_job_result(id)`: collects the job's result, or null if it isn't done yet. Failures are reported like
+//!   any other bind error.
+//! - `byondapi_job_cancel(id)`: asks the job to stop
+//! - `byondapi_job_wait(id, timeout)`: sleeps until the job is done and returns its result,
+//!   cancelling it if it takes longer than `timeout` deciseconds
+//!
+//! `#[byondapi::bind(job)]` does this for a whole bind: its arguments are converted on the main thread,
+//! then the body runs as a job and DM gets the job id back.
+//!
+//! Threads can't be killed, so cancellation and time limits only take effect when the job checks
+//! [`check_cancelled`], or when it finishes and its result is thrown away.
+//! Results that are never collected stay around until [`evict_uncollected`] throws them away.
+//!
+//! Requires the generated `bindings.dm` to be included in your DM project.
+use std::{
+    cell::RefCell,
+    collections::{btree_map::Entry, BTreeMap},
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{prelude::ByondValue, threadsync::run_caught, Error};
+
+inventory::submit! {
+    crate::binds::DmCode(r#"
+/proc/byondapi_job_wait(id, timeout)
+	var/give_up_at = isnull(timeout) ? null : world.time + timeout
+	while(!byondapi_job_done(id))
+		if(!isnull(give_up_at) && world.time >= give_up_at)
+			byondapi_job_cancel(id)
+			return null
+		sleep(world.tick_lag)
+	return byondapi_job_result(id)
+"#)
+}
+
+/// Turns a finished job's output into a [`ByondValue`], which can only happen on the main thread
+type JobOutput = Box<dyn FnOnce() -> Result<ByondValue, Error> + Send>;
+
+type JobWork = Box<dyn FnOnce() -> Result<JobOutput, Error> + Send>;
+
+enum JobState {
+    Queued,
+    Running,
+    Done(JobOutput),
+    Failed(Error),
+    Cancelled,
+    TimedOut,
+}
+
+struct Job {
+    state: JobState,
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    /// When the job stopped, for evicting results nobody collects
+    finished_at: Option<Instant>,
+}
+
+impl Job {
+    fn is_going(&self) -> bool {
+        matches!(self.state, JobState::Queued | JobState::Running)
+    }
+
+    fn finish(&mut self, state: JobState) {
+        self.state = state;
+        self.finished_at = Some(Instant::now());
+    }
+
+    /// Times the job out if it's still going past its deadline
+    fn check_deadline(&mut self) {
+        if self.is_going()
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.cancelled.store(true, Ordering::Relaxed);
+            self.finish(JobState::TimedOut);
+        }
+    }
+}
+
+static JOBS: Mutex<BTreeMap<u32, Job>> = Mutex::new(BTreeMap::new());
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Runs `f` on the job with this id, if there is one
+fn with_job<F: FnOnce(&mut Job) -> R, R>(id: u32, f: F) -> Option<R> {
+    JOBS.lock().unwrap().get_mut(&id).map(f)
+}
+
+struct QueuedJob {
+    id: u32,
+    work: JobWork,
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+thread_local! {
+    /// The cancellation flag and deadline of the job running on this thread
+    static CURRENT_JOB: RefCell<Option<(Arc<AtomicBool>, Option<Instant>)>> = const { RefCell::new(None) };
+}
+
+/// Starts the worker threads the first time a job is queued
+fn queue() -> &'static Mutex<Sender<QueuedJob>> {
+    static QUEUE: OnceLock<Mutex<Sender<QueuedJob>>> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<QueuedJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = thread::available_parallelism().map_or(2, |count| count.get());
+        for worker in 0..workers {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("byondapi-job-{worker}"))
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => run_job(job),
+                        Err(_) => return,
+                    }
+                })
+                .expect("Failed to spawn job worker thread");
+        }
+        Mutex::new(sender)
+    })
+}
+
+fn run_job(job: QueuedJob) {
+    let started = with_job(job.id, |entry| {
+        entry.check_deadline();
+        let queued = matches!(entry.state, JobState::Queued);
+        if queued {
+            entry.state = JobState::Running;
+        }
+        queued
+    });
+    if started != Some(true) {
+        return;
+    }
+
+    CURRENT_JOB.with(|current| *current.borrow_mut() = Some((job.cancelled.clone(), job.deadline)));
+    let result = run_caught(job.work).and_then(|result| result);
+    CURRENT_JOB.with(|current| *current.borrow_mut() = None);
+
+    with_job(job.id, |entry| {
+        // The job was collected or evicted while it ran, and its id has gone to another one
+        if !Arc::ptr_eq(&entry.cancelled, &job.cancelled) {
+            return;
+        }
+        entry.check_deadline();
+        // Anything else means the job was cancelled or timed out while it ran
+        if matches!(entry.state, JobState::Running) {
+            entry.finish(match result {
+                Ok(output) => JobState::Done(output),
+                Err(e) => JobState::Failed(e),
+            });
+        }
+    });
+}
+
+/// Queues `f` to run on a worker thread, returning its id straight away.
+///
+/// `f`'s result is turned into a [`ByondValue`] when it's collected, on the main thread. Errors are kept as their
+/// debug representation, in an [`Error::JobFailed`].
+pub fn spawn_job<F, R, E>(f: F) -> JobId
+where
+    F: FnOnce() -> Result<R, E> + Send + 'static,
+    R: TryInto<ByondValue> + Send + 'static,
+    Error: From<R::Error>,
+    E: Debug,
+{
+    spawn(None, f)
+}
+
+/// Like [`spawn_job`], but the job times out if it hasn't finished within `timeout` of being queued.
+pub fn spawn_job_with_timeout<F, R, E>(timeout: Duration, f: F) -> JobId
+where
+    F: FnOnce() -> Result<R, E> + Send + 'static,
+    R: TryInto<ByondValue> + Send + 'static,
+    Error: From<R::Error>,
+    E: Debug,
+{
+    spawn(Some(Instant::now() + timeout), f)
+}
+
+fn spawn<F, R, E>(deadline: Option<Instant>, f: F) -> JobId
+where
+    F: FnOnce() -> Result<R, E> + Send + 'static,
+    R: TryInto<ByondValue> + Send + 'static,
+    Error: From<R::Error>,
+    E: Debug,
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let id = {
+        let mut jobs = JOBS.lock().unwrap();
+        loop {
+            // Ids are sent to DM as numbers, so they have to stay within the range a float can represent exactly
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed) % (1 << 24);
+            // Skipping ids that are still around, so a wrapped id never overwrites a live job
+            if let Entry::Vacant(entry) = jobs.entry(id) {
+                entry.insert(Job {
+                    state: JobState::Queued,
+                    cancelled: cancelled.clone(),
+                    deadline,
+                    finished_at: None,
+                });
+                break id;
+            }
+        }
+    };
+
+    let work: JobWork = Box::new(move || match f() {
+        Ok(output) => Ok(Box::new(move || Ok(output.try_into()?)) as JobOutput),
+        Err(e) => Err(Error::JobFailed(format!("{e:?}"))),
+    });
+    let job = QueuedJob {
+        id,
+        work,
+        cancelled,
+        deadline,
+    };
+    // The workers never stop, so this can't fail
+    let _ = queue().lock().unwrap().send(job);
+    JobId(id)
+}
+
+/// Throws away the results of jobs that stopped more than `age` ago without being collected, returning how many were
+/// thrown away. Their ids become unknown.
+pub fn evict_uncollected(age: Duration) -> usize {
+    let mut jobs = JOBS.lock().unwrap();
+    let before = jobs.len();
+    jobs.retain(|_, job| {
+        job.check_deadline();
+        job.finished_at
+            .is_none_or(|finished_at| finished_at.elapsed() <= age)
+    });
+    before - jobs.len()
+}
+
+/// Checks if the job running on this thread has been cancelled or run out of time, always false outside of jobs.
+pub fn is_cancelled() -> bool {
+    check_cancelled().is_err()
+}
+
+/// Fails with [`Error::JobCancelled`] or [`Error::JobTimedOut`] if the job running on this thread should stop,
+/// for use with `?` in long loops.
+pub fn check_cancelled() -> Result<(), Error> {
+    CURRENT_JOB.with(|current| match &*current.borrow() {
+        Some((_, Some(deadline))) if Instant::now() >= *deadline => Err(Error::JobTimedOut),
+        Some((cancelled, _)) if cancelled.load(Ordering::Relaxed) => Err(Error::JobCancelled),
+        _ => Ok(()),
+    })
+}
+
+/// Where a job is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+    TimedOut,
+    /// The job doesn't exist, or its result has already been collected or evicted
+    Unknown,
+}
+
+impl JobStatus {
+    /// Whether the job has stopped, for whatever reason
+    pub fn is_finished(self) -> bool {
+        !matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::TimedOut => "timed out",
+            JobStatus::Unknown => "unknown",
+        }
+    }
+}
+
+/// Identifies a job started by [`spawn_job`], and is passed to DM as a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobId(u32);
+
+impl JobId {
+    pub fn status(self) -> JobStatus {
+        with_job(self.0, |job| {
+            job.check_deadline();
+            match job.state {
+                JobState::Queued => JobStatus::Queued,
+                JobState::Running => JobStatus::Running,
+                JobState::Done(_) => JobStatus::Done,
+                JobState::Failed(_) => JobStatus::Failed,
+                JobState::Cancelled => JobStatus::Cancelled,
+                JobState::TimedOut => JobStatus::TimedOut,
+            }
+        })
+        .unwrap_or(JobStatus::Unknown)
+    }
+
+    /// Asks the job to stop. If it hasn't finished yet, its result will be thrown away.
+    pub fn cancel(self) {
+        with_job(self.0, |job| {
+            job.cancelled.store(true, Ordering::Relaxed);
+            if job.is_going() {
+                job.finish(JobState::Cancelled);
+            }
+        });
+    }
+
+    /// Collects the job's result if it's finished, or returns [`None`] if it's still going. Must be called on the
+    /// main thread.
+    ///
+    /// Once collected, the job is forgotten.
+    pub fn take_result(self) -> Option<Result<ByondValue, Error>> {
+        let mut jobs = JOBS.lock().unwrap();
+        let job = jobs.get_mut(&self.0)?;
+        job.check_deadline();
+        if job.is_going() {
+            return None;
+        }
+        let job = jobs.remove(&self.0)?;
+        // Converting might call back into DM, which could check on jobs
+        drop(jobs);
+        Some(match job.state {
+            JobState::Done(output) => output(),
+            JobState::Failed(e) => Err(e),
+            JobState::Cancelled => Err(Error::JobCancelled),
+            JobState::TimedOut => Err(Error::JobTimedOut),
+            JobState::Queued | JobState::Running => unreachable!(),
+        })
+    }
+}
+
+impl From<JobId> for ByondValue {
+    fn from(id: JobId) -> Self {
+        ByondValue::new_num(id.0 as f32)
+    }
+}
+
+impl TryFrom<ByondValue> for JobId {
+    type Error = Error;
+
+    fn try_from(value: ByondValue) -> Result<Self, Self::Error> {
+        Ok(JobId(value.get_number()? as u32))
+    }
+}
+
+#[crate::bind]
+fn byondapi_job_status(id: ByondValue) -> Result<ByondValue, Error> {
+    ByondValue::new_str(JobId::try_from(id)?.status().name())
+}
+
+#[crate::bind]
+fn byondapi_job_done(id: ByondValue) -> Result<ByondValue, Error> {
+    Ok(JobId::try_from(id)?.status().is_finished().into())
+}
+
+#[crate::bind]
+fn byondapi_job_result(id: ByondValue) -> Result<ByondValue, Error> {
+    match JobId::try_from(id)?.take_result() {
+        Some(Err(e)) if matches!(e.root_cause(), Error::JobCancelled) => Ok(ByondValue::null()),
+        None => Ok(ByondValue::null()),
+        Some(result) => result,
+    }
+}
+
+#[crate::bind]
+fn byondapi_job_cancel(id: ByondValue) -> Result<ByondValue, Error> {
+    JobId::try_from(id)?.cancel();
+    Ok(ByondValue::null())
+}
@@ -1,9 +1,13 @@
 mod static_global;
 
+// Lets the macros, which refer to `::byondapi`, be used inside this crate too
+extern crate self as byondapi;
+
 ///Macros
 pub use byondapi_macros;
 pub use byondapi_macros::bind;
 pub use byondapi_macros::bind_raw_args;
+pub use byondapi_macros::byond_datum;
 pub use byondapi_macros::init;
 
 pub use binds::generate_bindings;
@@ -17,10 +21,21 @@ pub use error::Error;
 
 pub mod binds;
 pub mod byond_string;
+pub mod command_buffer;
+pub mod dmm;
 pub mod global_call;
+pub mod global_var;
+pub mod jobs;
+pub mod main_thread;
 pub mod prelude;
+pub mod queue;
+pub mod remote;
+pub mod sleeping_call;
+pub mod task;
 pub mod threadsync;
+pub mod tick_budget;
 pub mod value;
+pub mod world;
 
 use crate::value::ByondValue;
 /// # Safety
@@ -31,6 +46,8 @@ pub unsafe fn parse_args(
     argc: byondapi_sys::u4c,
     argv: *mut ByondValue,
 ) -> &'static mut [ByondValue] {
+    // Binds are only ever called on the main thread
+    main_thread::note_main_thread();
     //oh fuck off
     if argv.is_null() {
         return unsafe {
@@ -0,0 +1,214 @@
+//! An opt-in way to have the compiler check that values are only used on the main thread.
+//!
+//! [`ByondValue`] is [`Send`] so it can be handed around, but using it anywhere other than the main thread is
+//! unsound. A [`MainThread`] token proves code is running on the main thread, and can't be sent to other threads.
+//! Binds get one by taking a `&MainThread` argument, which isn't passed from DM, and
+//! [`thread_sync_with_token`](crate::threadsync::thread_sync_with_token) hands one to its closure.
+//!
+//! Values kept as [`MainThreadValue`]s can only be used through a token, as [`Local`]s which can't leave the thread:
+//! ```compile_fail
+//! # use byondapi::{main_thread::*, prelude::*};
+//! fn rename(main_thread: &MainThread, target: MainThreadValue) {
+//!     std::thread::spawn(move || {
+//!         target.get(main_thread).write_var("name", &ByondValue::null()) // Doesn't compile
+//!     });
+//! }
+//! ```
+//! [`Local`]s never hand out the value they hold, and values read through them come back as [`Local`]s too, so
+//! nothing reached through a token can be sent off either:
+//! ```compile_fail
+//! # use byondapi::{main_thread::*, prelude::*};
+//! fn leak(main_thread: &MainThread, target: MainThreadValue) {
+//!     let held = target.get(main_thread).read_var("held").unwrap();
+//!     std::thread::spawn(move || held.read_var("name")); // Doesn't compile
+//! }
+//! ```
+use std::{
+    marker::PhantomData,
+    sync::OnceLock,
+    thread::{self, ThreadId},
+};
+
+use crate::{prelude::ByondValue, sys::u4c, Error};
+
+/// The thread tokens have been made and binds have been called on, which is the main thread
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+/// Remembers the calling thread as the main thread, so [`MainThread::current`] works before any token is made
+pub(crate) fn note_main_thread() {
+    MAIN_THREAD.get_or_init(|| thread::current().id());
+}
+
+/// Proof that the code holding it is running on BYOND's main thread. Can't be sent to or shared with other threads.
+#[derive(Debug)]
+pub struct MainThread {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl MainThread {
+    /// Makes a token.
+    ///
+    /// # Safety
+    /// Must be called on the main thread.
+    pub unsafe fn new_unchecked() -> Self {
+        note_main_thread();
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Makes a token if this is the main thread, or returns [`None`] if it isn't or no bind has been called yet.
+    pub fn current() -> Option<Self> {
+        let main_thread = MAIN_THREAD.get()?;
+        (*main_thread == thread::current().id()).then_some(Self {
+            _not_send: PhantomData,
+        })
+    }
+}
+
+/// A value that can be sent between threads, but only used through a [`MainThread`] token.
+#[derive(Clone, Copy)]
+pub struct MainThreadValue(ByondValue);
+
+impl MainThreadValue {
+    pub fn new(value: ByondValue) -> Self {
+        Self(value)
+    }
+
+    /// Gets the value to use on the main thread
+    pub fn get<'a>(&self, _main_thread: &'a MainThread) -> Local<'a> {
+        Local {
+            value: self.0,
+            _main_thread: PhantomData,
+        }
+    }
+}
+
+impl From<ByondValue> for MainThreadValue {
+    fn from(value: ByondValue) -> Self {
+        Self::new(value)
+    }
+}
+
+/// A value that's usable because there's a [`MainThread`] token for as long as it lives. Can't be sent to or shared
+/// with other threads.
+///
+/// Has the same methods as [`ByondValue`] for using the value, but never hands out the value itself. Values read
+/// through it are [`Local`]s with the same lifetime.
+#[derive(Clone, Copy)]
+pub struct Local<'a> {
+    value: ByondValue,
+    _main_thread: PhantomData<&'a MainThread>,
+}
+
+impl<'a> Local<'a> {
+    /// Turns this back into something that can be sent to other threads
+    pub fn detach(self) -> MainThreadValue {
+        MainThreadValue(self.value)
+    }
+
+    /// Wraps a value reached through this one, tied to the same token
+    fn local(&self, value: ByondValue) -> Local<'a> {
+        Local {
+            value,
+            _main_thread: PhantomData,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.value.is_null()
+    }
+
+    pub fn is_num(&self) -> bool {
+        self.value.is_num()
+    }
+
+    pub fn is_str(&self) -> bool {
+        self.value.is_str()
+    }
+
+    pub fn is_list(&self) -> bool {
+        self.value.is_list()
+    }
+
+    pub fn is_true(&self) -> bool {
+        self.value.is_true()
+    }
+
+    pub fn get_bool(&self) -> Result<bool, Error> {
+        self.value.get_bool()
+    }
+
+    pub fn get_number(&self) -> Result<f32, Error> {
+        self.value.get_number()
+    }
+
+    pub fn get_string(&self) -> Result<String, Error> {
+        self.value.get_string()
+    }
+
+    pub fn read_var<T: Into<Vec<u8>>>(&self, name: T) -> Result<Local<'a>, Error> {
+        self.value.read_var(name).map(|value| self.local(value))
+    }
+
+    pub fn read_var_id(&self, name: u4c) -> Result<Local<'a>, Error> {
+        self.value.read_var_id(name).map(|value| self.local(value))
+    }
+
+    pub fn read_number<T: Into<Vec<u8>>>(&self, name: T) -> Result<f32, Error> {
+        self.value.read_number(name)
+    }
+
+    pub fn read_string<T: Into<Vec<u8>>>(&self, name: T) -> Result<String, Error> {
+        self.value.read_string(name)
+    }
+
+    pub fn write_var<T: Into<Vec<u8>>>(
+        &mut self,
+        name: T,
+        other: &ByondValue,
+    ) -> Result<(), Error> {
+        self.value.write_var(name, other)
+    }
+
+    pub fn write_var_id(&mut self, name: u4c, other: &ByondValue) -> Result<(), Error> {
+        self.value.write_var_id(name, other)
+    }
+
+    pub fn call<T: Into<Vec<u8>>>(&self, name: T, args: &[ByondValue]) -> Result<Local<'a>, Error> {
+        self.value.call(name, args).map(|value| self.local(value))
+    }
+
+    pub fn call_id(&self, name: u4c, args: &[ByondValue]) -> Result<Local<'a>, Error> {
+        self.value
+            .call_id(name, args)
+            .map(|value| self.local(value))
+    }
+
+    pub fn get_list_values(&self) -> Result<Vec<Local<'a>>, Error> {
+        Ok(self
+            .value
+            .get_list_values()?
+            .into_iter()
+            .map(|value| self.local(value))
+            .collect())
+    }
+
+    pub fn read_list_index<I: TryInto<ByondValue>>(&self, index: I) -> Result<Local<'a>, Error> {
+        self.value
+            .read_list_index(index)
+            .map(|value| self.local(value))
+    }
+
+    pub fn write_list_index<I: TryInto<ByondValue>, V: TryInto<ByondValue>>(
+        &mut self,
+        index: I,
+        value: V,
+    ) -> Result<(), Error> {
+        self.value.write_list_index(index, value)
+    }
+
+    pub fn push_list(&mut self, value: ByondValue) -> Result<(), Error> {
+        self.value.push_list(value)
+    }
+}
@@ -0,0 +1,147 @@
+//! Walking large regions of the map a slab at a time, instead of materialising every turf at once like
+//! [`byond_block`](super::byond_block) does.
+use super::{byond_block_into, world_corners, ByondXYZ};
+use crate::{prelude::ByondValue, Error};
+
+/// How [`BlockChunks`] splits up a region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkShape {
+    /// One row of turfs at a time
+    Rows,
+    /// One whole z-level at a time
+    ZLevels,
+    /// Squares of up to this many turfs per side, one z-level at a time
+    Tiles(i16),
+}
+
+/// One slab of turfs from [`BlockChunks`]
+#[derive(Debug)]
+pub struct Chunk<'a> {
+    /// The corner of this chunk with the lowest coordinates
+    pub min: ByondXYZ,
+    /// The corner of this chunk with the highest coordinates
+    pub max: ByondXYZ,
+    /// The turfs in this chunk, in [`byond_block`](super::byond_block) order
+    pub turfs: &'a [ByondValue],
+}
+
+/// Walks the turfs of a cuboid in chunks, reusing a single buffer for all of them.
+///
+/// This is a lending iterator, every chunk borrows the buffer, so use [`BlockChunks::next_chunk`] or
+/// [`BlockChunks::for_each_chunk`]:
+/// ```ignore
+/// let mut chunks = BlockChunks::zlevel(1, ChunkShape::Tiles(32))?;
+/// while let Some(chunk) = chunks.next_chunk() {
+///     for turf in chunk?.turfs {
+///         // ...
+///     }
+/// }
+/// ```
+pub struct BlockChunks {
+    min: (i32, i32, i32),
+    max: (i32, i32, i32),
+    size: (i32, i32),
+    next: Option<(i32, i32, i32)>,
+    buffer: Vec<ByondValue>,
+}
+
+impl BlockChunks {
+    /// Walks the cuboid between two corners, which can be in any order.
+    pub fn new(corner1: ByondXYZ, corner2: ByondXYZ, shape: ChunkShape) -> Self {
+        let min = (
+            corner1.x().min(corner2.x()) as i32,
+            corner1.y().min(corner2.y()) as i32,
+            corner1.z().min(corner2.z()) as i32,
+        );
+        let max = (
+            corner1.x().max(corner2.x()) as i32,
+            corner1.y().max(corner2.y()) as i32,
+            corner1.z().max(corner2.z()) as i32,
+        );
+        let width = max.0 - min.0 + 1;
+        let height = max.1 - min.1 + 1;
+        let size = match shape {
+            ChunkShape::Rows => (width, 1),
+            ChunkShape::ZLevels => (width, height),
+            ChunkShape::Tiles(side) => (
+                (side.max(1) as i32).min(width),
+                (side.max(1) as i32).min(height),
+            ),
+        };
+        Self {
+            min,
+            max,
+            size,
+            next: Some(min),
+            buffer: Vec::with_capacity((size.0 * size.1) as usize),
+        }
+    }
+
+    /// Walks nothing, for when the map has no turfs
+    fn empty(shape: ChunkShape) -> Self {
+        let mut chunks = Self::new(ByondXYZ::new(), ByondXYZ::new(), shape);
+        chunks.next = None;
+        chunks
+    }
+
+    /// Walks a whole z-level, reading its size from `world.maxx` and `world.maxy`. Walks nothing if the map has no
+    /// turfs.
+    pub fn zlevel(z: i16, shape: ChunkShape) -> Result<Self, Error> {
+        Ok(match world_corners()? {
+            Some((_, max)) => Self::new(
+                ByondXYZ::with_coords((1, 1, z)),
+                ByondXYZ::with_coords((max.x(), max.y(), z)),
+                shape,
+            ),
+            None => Self::empty(shape),
+        })
+    }
+
+    /// Walks every turf in the world, reading its size from `world.maxx`, `world.maxy` and `world.maxz`. Walks
+    /// nothing if the map has no turfs.
+    pub fn world(shape: ChunkShape) -> Result<Self, Error> {
+        Ok(match world_corners()? {
+            Some((min, max)) => Self::new(min, max, shape),
+            None => Self::empty(shape),
+        })
+    }
+
+    /// Reads the next chunk of turfs into the buffer, or returns [`None`] once the whole region has been walked.
+    pub fn next_chunk(&mut self) -> Option<Result<Chunk<'_>, Error>> {
+        let (x, y, z) = self.next?;
+        let chunk_max = (
+            (x + self.size.0 - 1).min(self.max.0),
+            (y + self.size.1 - 1).min(self.max.1),
+            z,
+        );
+
+        self.next = if chunk_max.0 < self.max.0 {
+            Some((chunk_max.0 + 1, y, z))
+        } else if chunk_max.1 < self.max.1 {
+            Some((self.min.0, chunk_max.1 + 1, z))
+        } else if z < self.max.2 {
+            Some((self.min.0, self.min.1, z + 1))
+        } else {
+            None
+        };
+
+        let min = ByondXYZ::with_coords((x as i16, y as i16, z as i16));
+        let max = ByondXYZ::with_coords((chunk_max.0 as i16, chunk_max.1 as i16, z as i16));
+        Some(byond_block_into(min, max, &mut self.buffer).map(|_| Chunk {
+            min,
+            max,
+            turfs: &self.buffer,
+        }))
+    }
+
+    /// Runs `f` on every remaining chunk, stopping at the first error.
+    pub fn for_each_chunk<F>(&mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(Chunk<'_>) -> Result<(), Error>,
+    {
+        while let Some(chunk) = self.next_chunk() {
+            f(chunk?)?;
+        }
+        Ok(())
+    }
+}
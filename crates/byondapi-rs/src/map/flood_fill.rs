@@ -0,0 +1,178 @@
+//! Flood fills and connected regions over coordinates, for finding rooms, zones and networks without DM recursion.
+//!
+//! Tiles are neighbours if they're next to each other in a cardinal direction on the same z-level.
+//! Whether two neighbours are actually connected is up to a `FnMut(ByondXYZ, ByondXYZ) -> bool`,
+//! which is asked about moving from a tile in the region to one next to it.
+//!
+//! ```ignore
+//! let room = flood_fill(start, None, |_, to| !walls.contains(&to))?;
+//! ```
+use std::collections::{HashSet, VecDeque};
+
+use super::{locate_many, world_corners, ByondXYZ, XYZRange};
+use crate::{prelude::ByondValue, Error};
+
+const DIRECTIONS: [(i16, i16); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+/// A set of connected tiles
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Region {
+    /// Every tile in the region, in [`byond_block`](super::byond_block) order
+    pub members: Vec<ByondXYZ>,
+    /// Every tile next to the region that isn't part of it, like the walls around a room, in the same order.
+    /// Tiles outside the bounds being searched are never included.
+    pub boundary: Vec<ByondXYZ>,
+}
+
+impl Region {
+    /// Checks if a tile is part of the region
+    pub fn contains(&self, xyz: ByondXYZ) -> bool {
+        self.members.binary_search(&xyz).is_ok()
+    }
+
+    /// Gets the turfs of every tile in the region
+    pub fn turfs(&self) -> Result<Vec<ByondValue>, Error> {
+        locate_many(&self.members)
+    }
+
+    /// Gets the turfs of every tile on the region's boundary
+    pub fn boundary_turfs(&self) -> Result<Vec<ByondValue>, Error> {
+        locate_many(&self.boundary)
+    }
+}
+
+/// Keeps track of which region every tile in the bounds belongs to
+struct Filler {
+    min: ByondXYZ,
+    max: ByondXYZ,
+    width: usize,
+    height: usize,
+    region_of: Vec<Option<usize>>,
+}
+
+impl Filler {
+    fn new(bounds: Option<(ByondXYZ, ByondXYZ)>) -> Result<Self, Error> {
+        let bounds = match bounds {
+            Some(bounds) => Some(bounds),
+            None => world_corners()?,
+        };
+        let Some((corner1, corner2)) = bounds else {
+            // The map has no turfs, min being past max keeps every tile out of bounds
+            return Ok(Self {
+                min: ByondXYZ::with_coords((1, 1, 1)),
+                max: ByondXYZ::new(),
+                width: 0,
+                height: 0,
+                region_of: Vec::new(),
+            });
+        };
+        let range = XYZRange::new(corner1, corner2);
+        let (min, max) = (range.min_corner(), range.max_corner());
+        Ok(Self {
+            min,
+            max,
+            width: (max.x() - min.x()) as usize + 1,
+            height: (max.y() - min.y()) as usize + 1,
+            region_of: vec![None; range.len()],
+        })
+    }
+
+    fn index(&self, xyz: ByondXYZ) -> Option<usize> {
+        if xyz.x() < self.min.x()
+            || xyz.y() < self.min.y()
+            || xyz.z() < self.min.z()
+            || xyz.x() > self.max.x()
+            || xyz.y() > self.max.y()
+            || xyz.z() > self.max.z()
+        {
+            return None;
+        }
+        let (dx, dy, dz) = xyz - self.min;
+        Some(dx as usize + dy as usize * self.width + dz as usize * self.width * self.height)
+    }
+
+    /// Fills out from `start`, marking everything reached as part of region `id`
+    fn fill<F>(&mut self, start: ByondXYZ, id: usize, connected: &mut F) -> Region
+    where
+        F: FnMut(ByondXYZ, ByondXYZ) -> bool,
+    {
+        let Some(index) = self.index(start) else {
+            return Region::default();
+        };
+        self.region_of[index] = Some(id);
+
+        let mut members = vec![start];
+        let mut boundary = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            for (dx, dy) in DIRECTIONS {
+                let Some(x) = current.x().checked_add(dx) else {
+                    continue;
+                };
+                let Some(y) = current.y().checked_add(dy) else {
+                    continue;
+                };
+                let next = ByondXYZ::with_coords((x, y, current.z()));
+                let Some(index) = self.index(next) else {
+                    continue;
+                };
+                if self.region_of[index] == Some(id) {
+                    continue;
+                }
+                if self.region_of[index].is_none() && connected(current, next) {
+                    self.region_of[index] = Some(id);
+                    members.push(next);
+                    queue.push_back(next);
+                } else {
+                    boundary.insert(next);
+                }
+            }
+        }
+
+        // A tile can be reached after another member found it unconnected
+        let mut boundary: Vec<ByondXYZ> = boundary
+            .into_iter()
+            .filter(|&xyz| self.index(xyz).and_then(|index| self.region_of[index]) != Some(id))
+            .collect();
+        members.sort_unstable();
+        boundary.sort_unstable();
+        Region { members, boundary }
+    }
+}
+
+/// Finds every tile connected to `start`, searching only within `bounds`, or the whole world if [`None`].
+///
+/// Returns an empty region if `start` is outside the bounds.
+pub fn flood_fill<F>(
+    start: ByondXYZ,
+    bounds: Option<(ByondXYZ, ByondXYZ)>,
+    mut connected: F,
+) -> Result<Region, Error>
+where
+    F: FnMut(ByondXYZ, ByondXYZ) -> bool,
+{
+    Ok(Filler::new(bounds)?.fill(start, 0, &mut connected))
+}
+
+/// Splits the cuboid between two corners into connected regions, so every tile is in exactly one of them.
+///
+/// Regions are in the order their first tile comes in [`byond_block`](super::byond_block) order.
+/// Tiles that aren't connected to anything, like walls, end up in regions of their own.
+pub fn connected_regions<F>(
+    corner1: ByondXYZ,
+    corner2: ByondXYZ,
+    mut connected: F,
+) -> Result<Vec<Region>, Error>
+where
+    F: FnMut(ByondXYZ, ByondXYZ) -> bool,
+{
+    let mut filler = Filler::new(Some((corner1, corner2)))?;
+    let mut regions = Vec::new();
+    for (index, xyz) in XYZRange::new(corner1, corner2).enumerate() {
+        if filler.region_of[index].is_none() {
+            let region = filler.fill(xyz, regions.len(), &mut connected);
+            regions.push(region);
+        }
+    }
+    Ok(regions)
+}
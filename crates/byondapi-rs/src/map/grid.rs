@@ -0,0 +1,153 @@
+//! A snapshot of every turf in the world, for looking turfs up by coordinate without going through BYOND each time.
+use super::{byond_block, ByondXYZ, XYZRange};
+use crate::{prelude::ByondValue, world::World, Error};
+
+/// Every turf in the world in a flat array, along with a `T` of your own for each of them.
+///
+/// Turf refs depend on the size of the map, so call [`TurfGrid::refresh`] whenever the map may have been resized,
+/// like at the start of every tick it's used in.
+/// ```ignore
+/// let mut grid = TurfGrid::<u32>::new()?;
+/// grid.refresh()?;
+/// if let Some(visits) = grid.data_mut(xyz) {
+///     *visits += 1;
+/// }
+/// let turf = grid.turf_at(xyz);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TurfGrid<T = ()> {
+    size: (i16, i16, i16),
+    turfs: Vec<ByondValue>,
+    data: Vec<T>,
+}
+
+impl<T: Default> TurfGrid<T> {
+    /// Snapshots every turf in the world, with default data for each
+    pub fn new() -> Result<Self, Error> {
+        let mut grid = Self {
+            size: (0, 0, 0),
+            turfs: Vec::new(),
+            data: Vec::new(),
+        };
+        grid.rebuild()?;
+        Ok(grid)
+    }
+
+    /// Checks `world.maxx`, `world.maxy` and `world.maxz`, and rebuilds the grid if any of them changed.
+    /// Returns whether it was rebuilt.
+    pub fn refresh(&mut self) -> Result<bool, Error> {
+        if world_size()? == self.size {
+            return Ok(false);
+        }
+        self.rebuild()?;
+        Ok(true)
+    }
+
+    /// Snapshots every turf in the world again. Data is kept for coordinates that still exist,
+    /// new coordinates get default data.
+    pub fn rebuild(&mut self) -> Result<(), Error> {
+        let size = world_size()?;
+        let (maxx, maxy, maxz) = size;
+        let turfs = if maxx > 0 && maxy > 0 && maxz > 0 {
+            byond_block(
+                ByondXYZ::with_coords((1, 1, 1)),
+                ByondXYZ::with_coords(size),
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let mut old_data: Vec<Option<T>> = std::mem::take(&mut self.data)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let data = if turfs.is_empty() {
+            Vec::new()
+        } else {
+            XYZRange::new(
+                ByondXYZ::with_coords((1, 1, 1)),
+                ByondXYZ::with_coords(size),
+            )
+            .map(|xyz| {
+                self.index(xyz)
+                    .and_then(|index| old_data[index].take())
+                    .unwrap_or_default()
+            })
+            .collect()
+        };
+
+        self.size = size;
+        self.turfs = turfs;
+        self.data = data;
+        Ok(())
+    }
+}
+
+impl<T> TurfGrid<T> {
+    /// The size of the world when this was last built, as `(maxx, maxy, maxz)`
+    pub fn size(&self) -> (i16, i16, i16) {
+        self.size
+    }
+
+    fn index(&self, xyz: ByondXYZ) -> Option<usize> {
+        let (maxx, maxy, maxz) = self.size;
+        let (x, y, z) = xyz.coordinates();
+        if x < 1 || y < 1 || z < 1 || x > maxx || y > maxy || z > maxz {
+            return None;
+        }
+        let (width, height) = (maxx as usize, maxy as usize);
+        Some((x - 1) as usize + (y - 1) as usize * width + (z - 1) as usize * width * height)
+    }
+
+    /// Checks if the coordinates are on the map
+    pub fn contains(&self, xyz: ByondXYZ) -> bool {
+        self.index(xyz).is_some()
+    }
+
+    /// Gets the turf at these coordinates, or [`None`] if they're off the map
+    pub fn turf_at(&self, xyz: ByondXYZ) -> Option<ByondValue> {
+        self.turfs.get(self.index(xyz)?).copied()
+    }
+
+    /// Gets the data for these coordinates, or [`None`] if they're off the map
+    pub fn data(&self, xyz: ByondXYZ) -> Option<&T> {
+        self.data.get(self.index(xyz)?)
+    }
+
+    /// Gets the data for these coordinates mutably, or [`None`] if they're off the map
+    pub fn data_mut(&mut self, xyz: ByondXYZ) -> Option<&mut T> {
+        let index = self.index(xyz)?;
+        self.data.get_mut(index)
+    }
+
+    /// Replaces the data for these coordinates, returning the old data, or [`None`] if they're off the map
+    pub fn set_data(&mut self, xyz: ByondXYZ, data: T) -> Option<T> {
+        Some(std::mem::replace(self.data_mut(xyz)?, data))
+    }
+
+    /// Every turf in the world in [`byond_block`] order
+    pub fn turfs(&self) -> &[ByondValue] {
+        &self.turfs
+    }
+
+    /// Iterates over the coordinates, turf and data of every cell, in [`byond_block`] order
+    pub fn iter(&self) -> impl Iterator<Item = (ByondXYZ, ByondValue, &T)> {
+        let range = (!self.turfs.is_empty()).then(|| {
+            XYZRange::new(
+                ByondXYZ::with_coords((1, 1, 1)),
+                ByondXYZ::with_coords(self.size),
+            )
+        });
+        range
+            .into_iter()
+            .flatten()
+            .zip(self.turfs.iter().copied())
+            .zip(&self.data)
+            .map(|((xyz, turf), data)| (xyz, turf, data))
+    }
+}
+
+fn world_size() -> Result<(i16, i16, i16), Error> {
+    let world = World::new();
+    Ok((world.maxx()?, world.maxy()?, world.maxz()?))
+}
@@ -0,0 +1,232 @@
+//! Lines, line of sight and field of view between coordinates, the parts of DM's `view()` that don't need BYOND.
+//!
+//! Everything here works on a single z-level, the one the line or view starts on.
+use std::collections::BTreeSet;
+
+use super::{grid::TurfGrid, locate_many, world_corners, ByondXYZ, XYZRange};
+use crate::{prelude::ByondValue, Error};
+
+/// Something that knows which tiles block sight.
+///
+/// Implemented for any `FnMut(ByondXYZ) -> bool`, and for a [`TurfGrid<bool>`] holding the opacity of each turf.
+pub trait Opacity {
+    fn is_opaque(&mut self, xyz: ByondXYZ) -> bool;
+
+    /// The corners of the area this covers, if it has any. Anything outside of it is treated as opaque.
+    fn bounds(&self) -> Option<(ByondXYZ, ByondXYZ)> {
+        None
+    }
+}
+
+impl<F: FnMut(ByondXYZ) -> bool> Opacity for F {
+    fn is_opaque(&mut self, xyz: ByondXYZ) -> bool {
+        self(xyz)
+    }
+}
+
+impl Opacity for TurfGrid<bool> {
+    fn is_opaque(&mut self, xyz: ByondXYZ) -> bool {
+        self.data(xyz).copied().unwrap_or(true)
+    }
+
+    fn bounds(&self) -> Option<(ByondXYZ, ByondXYZ)> {
+        Some((ByondXYZ::with_coords((1, 1, 1)), self.size().into()))
+    }
+}
+
+/// Gets every tile on the line between two points with Bresenham's algorithm, including both ends.
+pub fn line(from: ByondXYZ, to: ByondXYZ) -> Vec<ByondXYZ> {
+    let (mut x, mut y) = (from.x() as i32, from.y() as i32);
+    let (end_x, end_y) = (to.x() as i32, to.y() as i32);
+    let (dx, dy) = ((end_x - x).abs(), -(end_y - y).abs());
+    let (step_x, step_y) = ((end_x - x).signum(), (end_y - y).signum());
+    let mut error = dx + dy;
+
+    let mut points = Vec::with_capacity(dx.max(-dy) as usize + 1);
+    loop {
+        points.push(ByondXYZ::with_coords((x as i16, y as i16, from.z())));
+        if x == end_x && y == end_y {
+            return points;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Checks tiles in bounds, treating everything outside of them as opaque
+struct Bounded<'a, O: Opacity + ?Sized> {
+    opacity: &'a mut O,
+    min: ByondXYZ,
+    max: ByondXYZ,
+}
+
+impl<'a, O: Opacity + ?Sized> Bounded<'a, O> {
+    fn new(opacity: &'a mut O) -> Result<Self, Error> {
+        let bounds = match opacity.bounds() {
+            Some(bounds) => Some(bounds),
+            None => world_corners()?,
+        };
+        let (min, max) = match bounds {
+            Some((corner1, corner2)) => {
+                let bounds = XYZRange::new(corner1, corner2);
+                (bounds.min_corner(), bounds.max_corner())
+            }
+            // The map has no turfs, min being past max keeps every tile out of bounds
+            None => (ByondXYZ::with_coords((1, 1, 1)), ByondXYZ::new()),
+        };
+        Ok(Self { opacity, min, max })
+    }
+
+    fn contains(&self, x: i32, y: i32, z: i16) -> bool {
+        (self.min.x() as i32..=self.max.x() as i32).contains(&x)
+            && (self.min.y() as i32..=self.max.y() as i32).contains(&y)
+            && (self.min.z()..=self.max.z()).contains(&z)
+    }
+
+    fn is_opaque(&mut self, x: i32, y: i32, z: i16) -> bool {
+        !self.contains(x, y, z)
+            || self
+                .opacity
+                .is_opaque(ByondXYZ::with_coords((x as i16, y as i16, z)))
+    }
+}
+
+/// Walks the line from `from` to `to`, returning the first opaque tile after `from`, or [`None`] if nothing is in the way.
+/// `to` itself counts if it's opaque.
+pub fn raycast<O: Opacity + ?Sized>(
+    from: ByondXYZ,
+    to: ByondXYZ,
+    opacity: &mut O,
+) -> Result<Option<ByondXYZ>, Error> {
+    let mut bounded = Bounded::new(opacity)?;
+    Ok(line(from, to)
+        .into_iter()
+        .skip(1)
+        .find(|xyz| bounded.is_opaque(xyz.x() as i32, xyz.y() as i32, xyz.z())))
+}
+
+/// Checks if nothing opaque is on the line between two tiles. The tiles at either end can be opaque,
+/// like how walls can be seen.
+pub fn has_line_of_sight<O: Opacity + ?Sized>(
+    from: ByondXYZ,
+    to: ByondXYZ,
+    opacity: &mut O,
+) -> Result<bool, Error> {
+    let mut bounded = Bounded::new(opacity)?;
+    let points = line(from, to);
+    let between = &points[1..points.len().saturating_sub(1).max(1)];
+    Ok(!between
+        .iter()
+        .any(|xyz| bounded.is_opaque(xyz.x() as i32, xyz.y() as i32, xyz.z())))
+}
+
+/// Multipliers turning octant relative coordinates into offsets, as `(xx, xy, yx, yy)`
+const OCTANTS: [(i32, i32, i32, i32); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+/// Gets every tile visible from `center` within `radius` tiles, with recursive shadowcasting.
+///
+/// Like DM's `view()`, the area is a square rather than a circle, and opaque tiles that can be seen are included.
+/// The result is in [`byond_block`](super::byond_block) order.
+pub fn field_of_view<O: Opacity + ?Sized>(
+    center: ByondXYZ,
+    radius: u16,
+    opacity: &mut O,
+) -> Result<Vec<ByondXYZ>, Error> {
+    let mut bounded = Bounded::new(opacity)?;
+    let mut visible = BTreeSet::new();
+    let (x, y, z) = (center.x() as i32, center.y() as i32, center.z());
+    if !bounded.contains(x, y, z) {
+        return Ok(Vec::new());
+    }
+    visible.insert(center);
+
+    let mut shadowcaster = Shadowcaster {
+        bounded: &mut bounded,
+        visible: &mut visible,
+        center: (x, y, z),
+        radius: radius as i32,
+    };
+    for transform in OCTANTS {
+        shadowcaster.cast(1, 1.0, 0.0, transform);
+    }
+    Ok(visible.into_iter().collect())
+}
+
+/// Like [`field_of_view`], but gets the visible turfs instead of their coordinates
+pub fn visible_turfs<O: Opacity + ?Sized>(
+    center: ByondXYZ,
+    radius: u16,
+    opacity: &mut O,
+) -> Result<Vec<ByondValue>, Error> {
+    locate_many(&field_of_view(center, radius, opacity)?)
+}
+
+struct Shadowcaster<'a, 'b, O: Opacity + ?Sized> {
+    bounded: &'a mut Bounded<'b, O>,
+    visible: &'a mut BTreeSet<ByondXYZ>,
+    center: (i32, i32, i16),
+    radius: i32,
+}
+
+impl<O: Opacity + ?Sized> Shadowcaster<'_, '_, O> {
+    /// Scans one octant row by row, between two slopes, recursing to scan around anything opaque
+    fn cast(&mut self, row: i32, mut start: f32, end: f32, (xx, xy, yx, yy): (i32, i32, i32, i32)) {
+        if start < end {
+            return;
+        }
+        let (cx, cy, z) = self.center;
+        let mut new_start = 0.0;
+        for distance in row..=self.radius {
+            let dy = -distance;
+            let mut blocked = false;
+            for dx in -distance..=0 {
+                let (x, y) = (cx + dx * xx + dy * xy, cy + dx * yx + dy * yy);
+                let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+                let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+                if start < right_slope {
+                    continue;
+                } else if end > left_slope {
+                    break;
+                }
+
+                if self.bounded.contains(x, y, z) {
+                    self.visible
+                        .insert(ByondXYZ::with_coords((x as i16, y as i16, z)));
+                }
+
+                let opaque = self.bounded.is_opaque(x, y, z);
+                if blocked {
+                    if opaque {
+                        new_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = new_start;
+                    }
+                } else if opaque && distance < self.radius {
+                    blocked = true;
+                    self.cast(distance + 1, start, left_slope, (xx, xy, yx, yy));
+                    new_start = right_slope;
+                }
+            }
+            if blocked {
+                break;
+            }
+        }
+    }
+}
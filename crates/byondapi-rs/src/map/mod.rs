@@ -0,0 +1,404 @@
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+
+use byondapi_sys::CByondXYZ;
+
+use crate::{error::ErrorContext, prelude::ByondValue, static_global::byond, Error};
+
+pub mod chunks;
+pub mod flood_fill;
+pub mod grid;
+pub mod los;
+pub mod pathfinding;
+#[cfg(feature = "parallel")]
+pub mod pipeline;
+pub mod range;
+
+/// This struct is a little weird because we're actually responsible for initializing and freeing it ourselves, unlike
+/// all the rest.
+#[derive(Debug, Clone, Copy)]
+pub struct ByondXYZ(CByondXYZ);
+
+impl ByondXYZ {
+    pub fn new() -> Self {
+        Self(CByondXYZ {
+            x: 0,
+            y: 0,
+            z: 0,
+            junk: 0,
+        })
+    }
+
+    pub fn with_coords((x, y, z): (i16, i16, i16)) -> Self {
+        Self(CByondXYZ { x, y, z, junk: 0 })
+    }
+    pub fn coordinates(&self) -> (i16, i16, i16) {
+        (self.0.x, self.0.y, self.0.z)
+    }
+
+    pub fn x(&self) -> i16 {
+        self.0.x
+    }
+    pub fn y(&self) -> i16 {
+        self.0.y
+    }
+    pub fn z(&self) -> i16 {
+        self.0.z
+    }
+
+    pub fn set_x(&mut self, x: i16) {
+        self.0.x = x
+    }
+    pub fn set_y(&mut self, y: i16) {
+        self.0.y = y
+    }
+    pub fn set_z(&mut self, z: i16) {
+        self.0.z = z
+    }
+
+    /// Corresponds to [`dm::get_dist`](https://www.byond.com/docs/ref/#/proc/get_dist), the chebyshev distance.
+    /// Like all the distance functions here, this only looks at x and y.
+    pub fn get_dist(&self, other: &ByondXYZ) -> u32 {
+        let (dx, dy) = self.deltas(other);
+        dx.max(dy)
+    }
+
+    /// Distance counting only cardinal steps
+    pub fn manhattan_dist(&self, other: &ByondXYZ) -> u32 {
+        let (dx, dy) = self.deltas(other);
+        dx + dy
+    }
+
+    /// Straight line distance
+    pub fn euclidean_dist(&self, other: &ByondXYZ) -> f32 {
+        let (dx, dy) = self.deltas(other);
+        (dx as f32).hypot(dy as f32)
+    }
+
+    fn deltas(&self, other: &ByondXYZ) -> (u32, u32) {
+        (
+            (self.0.x as i32 - other.0.x as i32).unsigned_abs(),
+            (self.0.y as i32 - other.0.y as i32).unsigned_abs(),
+        )
+    }
+
+    /// Iterates over every coordinate in the cuboid between this and `other`, in the same order as [`byond_block`]
+    pub fn range_to(&self, other: &ByondXYZ) -> XYZRange {
+        XYZRange::new(*self, *other)
+    }
+}
+
+impl Default for ByondXYZ {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for ByondXYZ {
+    fn eq(&self, other: &Self) -> bool {
+        self.coordinates() == other.coordinates()
+    }
+}
+
+impl Eq for ByondXYZ {}
+
+impl Hash for ByondXYZ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.coordinates().hash(state)
+    }
+}
+
+/// Ordered by z, then y, then x, the same order [`byond_block`] returns turfs in
+impl Ord for ByondXYZ {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.z, self.0.y, self.0.x).cmp(&(other.0.z, other.0.y, other.0.x))
+    }
+}
+
+impl PartialOrd for ByondXYZ {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<(i16, i16, i16)> for ByondXYZ {
+    fn from(coords: (i16, i16, i16)) -> Self {
+        Self::with_coords(coords)
+    }
+}
+
+impl From<ByondXYZ> for (i16, i16, i16) {
+    fn from(xyz: ByondXYZ) -> Self {
+        xyz.coordinates()
+    }
+}
+
+/// Offsets the coordinates
+impl Add<(i16, i16, i16)> for ByondXYZ {
+    type Output = ByondXYZ;
+
+    fn add(self, (x, y, z): (i16, i16, i16)) -> Self::Output {
+        Self::with_coords((self.0.x + x, self.0.y + y, self.0.z + z))
+    }
+}
+
+impl AddAssign<(i16, i16, i16)> for ByondXYZ {
+    fn add_assign(&mut self, offset: (i16, i16, i16)) {
+        *self = *self + offset
+    }
+}
+
+/// Offsets the coordinates
+impl Sub<(i16, i16, i16)> for ByondXYZ {
+    type Output = ByondXYZ;
+
+    fn sub(self, (x, y, z): (i16, i16, i16)) -> Self::Output {
+        Self::with_coords((self.0.x - x, self.0.y - y, self.0.z - z))
+    }
+}
+
+impl SubAssign<(i16, i16, i16)> for ByondXYZ {
+    fn sub_assign(&mut self, offset: (i16, i16, i16)) {
+        *self = *self - offset
+    }
+}
+
+/// Gets the offset between two coordinates
+impl Sub for ByondXYZ {
+    type Output = (i16, i16, i16);
+
+    fn sub(self, other: ByondXYZ) -> Self::Output {
+        (
+            self.0.x - other.0.x,
+            self.0.y - other.0.y,
+            self.0.z - other.0.z,
+        )
+    }
+}
+
+/// Iterator over every coordinate in a cuboid, x first, then y, then z. The same order [`byond_block`] uses.
+#[derive(Debug, Clone)]
+pub struct XYZRange {
+    min: (i16, i16, i16),
+    max: (i16, i16, i16),
+    next: Option<(i16, i16, i16)>,
+}
+
+impl XYZRange {
+    /// Creates an iterator over the cuboid between two corners, which can be in any order
+    pub fn new(corner1: ByondXYZ, corner2: ByondXYZ) -> Self {
+        let min = (
+            corner1.x().min(corner2.x()),
+            corner1.y().min(corner2.y()),
+            corner1.z().min(corner2.z()),
+        );
+        let max = (
+            corner1.x().max(corner2.x()),
+            corner1.y().max(corner2.y()),
+            corner1.z().max(corner2.z()),
+        );
+        Self {
+            min,
+            max,
+            next: Some(min),
+        }
+    }
+
+    /// The corner with the lowest coordinates
+    pub fn min_corner(&self) -> ByondXYZ {
+        self.min.into()
+    }
+
+    /// The corner with the highest coordinates
+    pub fn max_corner(&self) -> ByondXYZ {
+        self.max.into()
+    }
+}
+
+impl Iterator for XYZRange {
+    type Item = ByondXYZ;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next?;
+        let (x, y, z) = current;
+        self.next = if x < self.max.0 {
+            Some((x + 1, y, z))
+        } else if y < self.max.1 {
+            Some((self.min.0, y + 1, z))
+        } else if z < self.max.2 {
+            Some((self.min.0, self.min.1, z + 1))
+        } else {
+            None
+        };
+        Some(current.into())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = match self.next {
+            Some((x, y, z)) => {
+                let width = (self.max.0 - self.min.0) as usize + 1;
+                let height = (self.max.1 - self.min.1) as usize + 1;
+                let row_rest = (self.max.0 - x) as usize + 1;
+                let plane_rest = (self.max.1 - y) as usize * width;
+                let levels_rest = (self.max.2 - z) as usize * width * height;
+                row_rest + plane_rest + levels_rest
+            }
+            None => 0,
+        };
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for XYZRange {}
+
+/// The corners of the whole map, or [`None`] if there are no turfs because `world.maxx`, `world.maxy` or
+/// `world.maxz` is 0
+pub(crate) fn world_corners() -> Result<Option<(ByondXYZ, ByondXYZ)>, Error> {
+    let world = crate::world::World::new();
+    let max = (world.maxx()?, world.maxy()?, world.maxz()?);
+    if max.0 < 1 || max.1 < 1 || max.2 < 1 {
+        return Ok(None);
+    }
+    Ok(Some((
+        ByondXYZ::with_coords((1, 1, 1)),
+        ByondXYZ::with_coords(max),
+    )))
+}
+
+/// Corresponds to [`dm::block`](https://www.byond.com/docs/ref/#/proc/block)
+/// Gets a list of turfs in a square zone between the two provided corners.
+pub fn byond_block(corner1: ByondXYZ, corner2: ByondXYZ) -> Result<Vec<ByondValue>, Error> {
+    use std::cell::RefCell;
+
+    thread_local! {
+        static BUFFER: RefCell<Vec<ByondValue>> = RefCell::new(Vec::with_capacity(1));
+    }
+
+    BUFFER.with_borrow_mut(|buff| -> Result<Vec<ByondValue>, Error> {
+        byond_block_into(corner1, corner2, buff)?;
+        Ok(std::mem::take(buff))
+    })
+}
+
+/// Same as [`byond_block`], but fills `buff` instead of allocating a new [`Vec`], so one buffer can be reused for
+/// many blocks. Anything already in `buff` is cleared.
+pub fn byond_block_into(
+    corner1: ByondXYZ,
+    corner2: ByondXYZ,
+    buff: &mut Vec<ByondValue>,
+) -> Result<(), Error> {
+    let context = |e: Error| {
+        e.with_context(ErrorContext::new("block").name(format!(
+            "{:?} to {:?}",
+            corner1.coordinates(),
+            corner2.coordinates()
+        )))
+    };
+    buff.clear();
+    let mut len = buff.capacity() as u32;
+    // Safety: buffer capacity is passed to byond, which makes sure it writes in-bound
+    let initial_res =
+        unsafe { byond().Byond_Block(&corner1.0, &corner2.0, buff.as_mut_ptr().cast(), &mut len) };
+    match (initial_res, len) {
+        (false, 1..) => {
+            buff.reserve_exact(len as usize);
+            // Safety: buffer capacity is passed to byond, which makes sure it writes in-bound
+            unsafe {
+                map_byond_error!(byond().Byond_Block(
+                    &corner1.0,
+                    &corner2.0,
+                    buff.as_mut_ptr().cast(),
+                    &mut len
+                ))
+                .map_err(context)?
+            };
+
+            // Safety: buffer should be written to at this point
+            unsafe { buff.set_len(len as usize) };
+            Ok(())
+        }
+        (true, _) => {
+            // Safety: buffer should be written to at this point
+            unsafe { buff.set_len(len as usize) };
+            Ok(())
+        }
+        (false, 0) => Err(context(Error::get_last_byond_error())),
+    }
+}
+
+/// Corresponds to the first variation of [`dm::locate(Type) in Container`](https://www.byond.com/docs/ref/#/proc/locate)
+/// Finds an object prototype or tag within the haystack, usually used for finding objects within a turf/area/etc
+pub fn byond_locatein(needle: &ByondValue, haystack: &ByondValue) -> Result<ByondValue, Error> {
+    let mut output = ByondValue::new();
+
+    // Safety: needle, haystack, and output must be initialized, we take care of this.
+    unsafe {
+        map_byond_error!(byond().Byond_LocateIn(&needle.0, &haystack.0, &mut output.0)).map_err(
+            |e| {
+                e.with_context(
+                    ErrorContext::new("locate")
+                        .name_value(needle)
+                        .target(*haystack),
+                )
+            },
+        )?
+    };
+
+    Ok(output)
+}
+
+/// Corresponds to the third and forth variation of [`dm::locate(Tag/TextRef)`](https://www.byond.com/docs/ref/#/proc/locate)
+/// Finds an object prototype or tag within the world.
+pub fn byond_locateby(target: &ByondValue) -> Result<ByondValue, Error> {
+    let mut output = ByondValue::new();
+
+    // Safety: target and output must be initialized, we take care of this.
+    unsafe {
+        map_byond_error!(byond().Byond_LocateIn(&target.0, std::ptr::null(), &mut output.0))
+            .map_err(|e| e.with_context(ErrorContext::new("locate").name_value(target)))?
+    };
+
+    Ok(output)
+}
+
+/// Corresponds to the second variation of [`dm::locate(X,Y,Z)`](https://www.byond.com/docs/ref/#/proc/locate)
+/// Finds a turf at the given coordinates.
+pub fn byond_locatexyz(coords: ByondXYZ) -> Result<ByondValue, Error> {
+    let mut output = ByondValue::new();
+
+    // Safety: coords and output must be initialized, we take care of this.
+    unsafe {
+        map_byond_error!(byond().Byond_LocateXYZ(&coords.0, &mut output.0)).map_err(|e| {
+            e.with_context(ErrorContext::new("locate").name(format!("{:?}", coords.coordinates())))
+        })?
+    };
+
+    Ok(output)
+}
+
+/// Corresponds to accessing [`atom.loc`](https://www.byond.com/docs/ref/#/atom/var/loc)
+/// Gets the location of the target, which will be 0,0,0 if the atom is not directly on a turf.
+pub fn byond_xyz(target: &ByondValue) -> Result<ByondXYZ, Error> {
+    let mut output = ByondXYZ::new();
+
+    // Safety: target and output must be initialized, we take care of this.
+    unsafe {
+        map_byond_error!(byond().Byond_XYZ(&target.0, &mut output.0))
+            .map_err(|e| e.with_context(ErrorContext::new("xyz").target(*target)))?
+    };
+
+    Ok(output)
+}
+
+/// Calls [`byond_locatexyz`] for each of the coordinates, returning the turfs in the same order.
+pub fn locate_many(coords: &[ByondXYZ]) -> Result<Vec<ByondValue>, Error> {
+    coords.iter().map(|xyz| byond_locatexyz(*xyz)).collect()
+}
+
+/// Calls [`byond_xyz`] for each of the values, returning the coordinates in the same order.
+pub fn xyz_many(targets: &[ByondValue]) -> Result<Vec<ByondXYZ>, Error> {
+    targets.iter().map(byond_xyz).collect()
+}
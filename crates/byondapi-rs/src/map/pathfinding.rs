@@ -0,0 +1,683 @@
+//! Finding paths between turfs, with A* or jump point search.
+//!
+//! Both searches work on coordinates, and only ask a [`Passability`] what it costs to enter each tile.
+//! Paths come back as a list of [`ByondXYZ`], use [`locate_many`](super::locate_many) to turn them into turfs.
+//!
+//! ```ignore
+//! let mut grid = CostGrid::from_turfs(corner1, corner2, |turf| {
+//!     (!turf.read_var_id(byond_string!("density")).ok()?.get_bool().ok()?).then_some(1.0)
+//! })?;
+//! let path = jps_path(start, goal, &mut grid, &PathOptions::default())?;
+//! ```
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    f32::consts::SQRT_2,
+};
+
+use super::{byond_block, world_corners, ByondXYZ, XYZRange};
+use crate::{prelude::ByondValue, Error};
+
+/// Something that knows which tiles can be walked through, and how much they cost to walk through.
+///
+/// Implemented for any `FnMut(ByondXYZ) -> Option<f32>` and for [`CostGrid`].
+pub trait Passability {
+    /// Gets the cost of entering a tile, per tile of distance moved, or [`None`] if it can't be entered.
+    ///
+    /// Costs should be at least 1, or A* won't always find the shortest path.
+    /// Jump point search only looks at whether this is [`None`].
+    fn cost(&mut self, xyz: ByondXYZ) -> Option<f32>;
+
+    /// The corners of the area this covers, if it has any. Searches never leave it.
+    fn bounds(&self) -> Option<(ByondXYZ, ByondXYZ)> {
+        None
+    }
+}
+
+impl<F: FnMut(ByondXYZ) -> Option<f32>> Passability for F {
+    fn cost(&mut self, xyz: ByondXYZ) -> Option<f32> {
+        self(xyz)
+    }
+}
+
+/// Precomputed costs for every tile in a cuboid, anything outside of it can't be entered.
+#[derive(Debug, Clone)]
+pub struct CostGrid {
+    tiles: XYZRange,
+    width: usize,
+    height: usize,
+    costs: Vec<Option<f32>>,
+}
+
+impl CostGrid {
+    /// Creates a grid covering the cuboid between two corners, with every tile costing `default`.
+    pub fn new(corner1: ByondXYZ, corner2: ByondXYZ, default: Option<f32>) -> Self {
+        let tiles = XYZRange::new(corner1, corner2);
+        let (min, max) = (tiles.min_corner(), tiles.max_corner());
+        let width = (max.x() - min.x()) as usize + 1;
+        let height = (max.y() - min.y()) as usize + 1;
+        let costs = vec![default; tiles.len()];
+        Self {
+            tiles,
+            width,
+            height,
+            costs,
+        }
+    }
+
+    /// Creates a grid covering the cuboid between two corners, getting the cost of each tile from its turf.
+    pub fn from_turfs<F>(corner1: ByondXYZ, corner2: ByondXYZ, mut f: F) -> Result<Self, Error>
+    where
+        F: FnMut(&ByondValue) -> Option<f32>,
+    {
+        let mut grid = Self::new(corner1, corner2, None);
+        let turfs = byond_block(corner1, corner2)?;
+        for (cost, turf) in grid.costs.iter_mut().zip(&turfs) {
+            *cost = f(turf);
+        }
+        Ok(grid)
+    }
+
+    fn index(&self, xyz: ByondXYZ) -> Option<usize> {
+        let (min, max) = (self.tiles.min_corner(), self.tiles.max_corner());
+        if xyz.x() < min.x()
+            || xyz.y() < min.y()
+            || xyz.z() < min.z()
+            || xyz.x() > max.x()
+            || xyz.y() > max.y()
+            || xyz.z() > max.z()
+        {
+            return None;
+        }
+        let (dx, dy, dz) = xyz - min;
+        Some(dx as usize + dy as usize * self.width + dz as usize * self.width * self.height)
+    }
+
+    /// Gets the cost of a tile, [`None`] if it can't be entered or is outside the grid.
+    pub fn get(&self, xyz: ByondXYZ) -> Option<f32> {
+        self.costs[self.index(xyz)?]
+    }
+
+    /// Sets the cost of a tile, does nothing if it's outside the grid.
+    pub fn set(&mut self, xyz: ByondXYZ, cost: Option<f32>) {
+        if let Some(index) = self.index(xyz) {
+            self.costs[index] = cost;
+        }
+    }
+}
+
+impl Passability for CostGrid {
+    fn cost(&mut self, xyz: ByondXYZ) -> Option<f32> {
+        self.get(xyz)
+    }
+
+    fn bounds(&self) -> Option<(ByondXYZ, ByondXYZ)> {
+        Some((self.tiles.min_corner(), self.tiles.max_corner()))
+    }
+}
+
+/// When a path may move diagonally
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiagonalMode {
+    /// Only ever move in cardinal directions
+    Never,
+    /// Move diagonally whenever the destination can be entered, like BYOND's own diagonal movement
+    #[default]
+    Always,
+    /// Move diagonally only if at least one of the two tiles being cut past can be entered
+    IfOneCardinalOpen,
+    /// Move diagonally only if both of the tiles being cut past can be entered, so corners are never cut
+    IfBothCardinalsOpen,
+}
+
+/// Settings shared by [`astar_path`] and [`jps_path`]
+#[derive(Debug, Clone, Default)]
+pub struct PathOptions {
+    pub diagonals: DiagonalMode,
+    /// The most steps a path may take, unlimited if [`None`]. Taking a link counts as one step.
+    pub max_distance: Option<u32>,
+    /// The area to search in. If [`None`], uses the bounds of the [`Passability`], or failing that the whole world.
+    pub bounds: Option<(ByondXYZ, ByondXYZ)>,
+    /// Extra one-way connections between tiles, like stairs or ladders between z-levels, and what they cost to take.
+    ///
+    /// Paths that use these aren't always the cheapest. The search estimates how far it is left to go by walking
+    /// distance on x and y, ignoring z, and a link can be a shortcut it doesn't expect.
+    pub links: HashMap<ByondXYZ, Vec<(ByondXYZ, f32)>>,
+}
+
+impl PathOptions {
+    /// Adds a one-way connection from one tile to another
+    pub fn link(&mut self, from: ByondXYZ, to: ByondXYZ, cost: f32) {
+        self.links.entry(from).or_default().push((to, cost));
+    }
+
+    /// Adds a connection between two tiles that can be taken either way
+    pub fn link_both_ways(&mut self, first: ByondXYZ, second: ByondXYZ, cost: f32) {
+        self.link(first, second, cost);
+        self.link(second, first, cost);
+    }
+}
+
+/// Finds the cheapest path between two tiles with A*.
+///
+/// The path includes `goal` but not `start`, so it's empty if they're the same tile.
+/// Returns [`None`] if there is no path.
+pub fn astar_path<P: Passability + ?Sized>(
+    start: ByondXYZ,
+    goal: ByondXYZ,
+    passability: &mut P,
+    options: &PathOptions,
+) -> Result<Option<Vec<ByondXYZ>>, Error> {
+    let mut search = Search::new(start, goal, passability, options)?;
+    if !search.walkable(goal) {
+        return Ok(None);
+    }
+    while let Some((current, g, steps)) = search.pop() {
+        if current == goal {
+            return Ok(Some(search.path()));
+        }
+        for &(dx, dy) in search.directions() {
+            if (dx != 0 && dy != 0) && !search.diagonal_allowed(current, dx, dy) {
+                continue;
+            }
+            let next = offset(current, dx, dy);
+            let Some(cost) = search.cost(next) else {
+                continue;
+            };
+            let distance = if dx != 0 && dy != 0 { SQRT_2 } else { 1.0 };
+            search.push(next, Some(current), g + cost * distance, steps + 1, false);
+        }
+        search.push_links(current, g, steps);
+    }
+    Ok(None)
+}
+
+/// Finds the shortest path between two tiles with jump point search, which skips over open areas much faster than A*.
+///
+/// Only looks at whether tiles can be entered, every tile costs the same.
+/// The path includes `goal` but not `start`, so it's empty if they're the same tile.
+/// Returns [`None`] if there is no path.
+pub fn jps_path<P: Passability + ?Sized>(
+    start: ByondXYZ,
+    goal: ByondXYZ,
+    passability: &mut P,
+    options: &PathOptions,
+) -> Result<Option<Vec<ByondXYZ>>, Error> {
+    let mut search = Search::new(start, goal, passability, options)?;
+    if !search.walkable(goal) {
+        return Ok(None);
+    }
+    while let Some((current, g, steps)) = search.pop() {
+        if current == goal {
+            return Ok(Some(search.path()));
+        }
+        for (x, y) in search.jps_neighbours(current) {
+            let (dx, dy) = (
+                (x - current.x() as i32).signum(),
+                (y - current.y() as i32).signum(),
+            );
+            let Some((jx, jy)) = search.jump(x, y, current.z(), dx, dy) else {
+                continue;
+            };
+            let jump_point = ByondXYZ::with_coords((jx as i16, jy as i16, current.z()));
+            let distance = octile(current, jump_point);
+            let jump_steps = current.get_dist(&jump_point);
+            search.push(
+                jump_point,
+                Some(current),
+                g + distance,
+                steps + jump_steps,
+                false,
+            );
+        }
+        search.push_links(current, g, steps);
+    }
+    Ok(None)
+}
+
+fn offset(xyz: ByondXYZ, dx: i32, dy: i32) -> ByondXYZ {
+    ByondXYZ::with_coords((
+        (xyz.x() as i32 + dx) as i16,
+        (xyz.y() as i32 + dy) as i16,
+        xyz.z(),
+    ))
+}
+
+/// The length of the shortest 8 directional path between two tiles on open ground
+fn octile(from: ByondXYZ, to: ByondXYZ) -> f32 {
+    let (dx, dy) = (from.x().abs_diff(to.x()), from.y().abs_diff(to.y()));
+    let (short, long) = (dx.min(dy) as f32, dx.max(dy) as f32);
+    long - short + short * SQRT_2
+}
+
+const CARDINALS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const ALL_DIRECTIONS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+    (1, 1),
+    (1, -1),
+    (-1, -1),
+    (-1, 1),
+];
+
+struct Node {
+    g: f32,
+    steps: u32,
+    parent: Option<ByondXYZ>,
+    /// Whether this was reached through one of [`PathOptions::links`], rather than by walking
+    via_link: bool,
+    closed: bool,
+}
+
+/// An entry in the open set, ordered so the [`BinaryHeap`] pops the lowest estimated total cost first
+struct Open {
+    f: f32,
+    g: f32,
+    xyz: ByondXYZ,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Ties go to whichever got further, which is usually closer to the goal
+        other
+            .f
+            .total_cmp(&self.f)
+            .then_with(|| self.g.total_cmp(&other.g))
+    }
+}
+
+struct Search<'a, P: Passability + ?Sized> {
+    passability: &'a mut P,
+    options: &'a PathOptions,
+    goal: ByondXYZ,
+    min: ByondXYZ,
+    max: ByondXYZ,
+    costs: HashMap<ByondXYZ, Option<f32>>,
+    open: BinaryHeap<Open>,
+    nodes: HashMap<ByondXYZ, Node>,
+}
+
+impl<'a, P: Passability + ?Sized> Search<'a, P> {
+    fn new(
+        start: ByondXYZ,
+        goal: ByondXYZ,
+        passability: &'a mut P,
+        options: &'a PathOptions,
+    ) -> Result<Self, Error> {
+        let bounds = match options.bounds.or_else(|| passability.bounds()) {
+            Some(bounds) => Some(bounds),
+            None => world_corners()?,
+        };
+        let (min, max) = match bounds {
+            Some((corner1, corner2)) => {
+                let bounds = XYZRange::new(corner1, corner2);
+                (bounds.min_corner(), bounds.max_corner())
+            }
+            // The map has no turfs, min being past max keeps every tile out of bounds
+            None => (ByondXYZ::with_coords((1, 1, 1)), ByondXYZ::new()),
+        };
+        let mut search = Self {
+            passability,
+            options,
+            goal,
+            min,
+            max,
+            costs: HashMap::new(),
+            open: BinaryHeap::new(),
+            nodes: HashMap::new(),
+        };
+        search.push(start, None, 0.0, 0, false);
+        Ok(search)
+    }
+
+    fn cost(&mut self, xyz: ByondXYZ) -> Option<f32> {
+        let (min, max) = (self.min, self.max);
+        if xyz.x() < min.x()
+            || xyz.y() < min.y()
+            || xyz.z() < min.z()
+            || xyz.x() > max.x()
+            || xyz.y() > max.y()
+            || xyz.z() > max.z()
+        {
+            return None;
+        }
+        *self
+            .costs
+            .entry(xyz)
+            .or_insert_with(|| self.passability.cost(xyz))
+    }
+
+    fn walkable(&mut self, xyz: ByondXYZ) -> bool {
+        self.cost(xyz).is_some()
+    }
+
+    /// [`Self::walkable`], but on raw coordinates that may be out of range of an i16
+    fn walkable_at(&mut self, x: i32, y: i32, z: i16) -> bool {
+        match (i16::try_from(x), i16::try_from(y)) {
+            (Ok(x), Ok(y)) => self.walkable(ByondXYZ::with_coords((x, y, z))),
+            _ => false,
+        }
+    }
+
+    fn heuristic(&self, xyz: ByondXYZ) -> f32 {
+        match self.options.diagonals {
+            DiagonalMode::Never => xyz.manhattan_dist(&self.goal) as f32,
+            _ => octile(xyz, self.goal),
+        }
+    }
+
+    fn directions(&self) -> &'static [(i32, i32)] {
+        match self.options.diagonals {
+            DiagonalMode::Never => &CARDINALS,
+            _ => &ALL_DIRECTIONS,
+        }
+    }
+
+    fn diagonal_allowed(&mut self, from: ByondXYZ, dx: i32, dy: i32) -> bool {
+        match self.options.diagonals {
+            DiagonalMode::Never => false,
+            DiagonalMode::Always => true,
+            DiagonalMode::IfOneCardinalOpen => {
+                self.walkable(offset(from, dx, 0)) || self.walkable(offset(from, 0, dy))
+            }
+            DiagonalMode::IfBothCardinalsOpen => {
+                self.walkable(offset(from, dx, 0)) && self.walkable(offset(from, 0, dy))
+            }
+        }
+    }
+
+    /// Records a route to a tile if it's better than what we already have
+    fn push(
+        &mut self,
+        xyz: ByondXYZ,
+        parent: Option<ByondXYZ>,
+        g: f32,
+        steps: u32,
+        via_link: bool,
+    ) {
+        if self.options.max_distance.is_some_and(|max| steps > max) {
+            return;
+        }
+        if let Some(node) = self.nodes.get(&xyz) {
+            // With a max distance, a costlier route that took fewer steps can still get places the cheaper one can't
+            let steps_matter = self.options.max_distance.is_some();
+            if node.g <= g && (!steps_matter || node.steps <= steps) {
+                return;
+            }
+        }
+        self.nodes.insert(
+            xyz,
+            Node {
+                g,
+                steps,
+                parent,
+                via_link,
+                closed: false,
+            },
+        );
+        let f = g + self.heuristic(xyz);
+        self.open.push(Open { f, g, xyz });
+    }
+
+    fn push_links(&mut self, from: ByondXYZ, g: f32, steps: u32) {
+        let Some(links) = self.options.links.get(&from) else {
+            return;
+        };
+        for &(to, cost) in links {
+            if self.walkable(to) {
+                self.push(to, Some(from), g + cost, steps + 1, true);
+            }
+        }
+    }
+
+    /// Takes the most promising open tile and closes it, returning its cost and steps so far
+    fn pop(&mut self) -> Option<(ByondXYZ, f32, u32)> {
+        while let Some(Open { g, xyz, .. }) = self.open.pop() {
+            let node = self.nodes.get_mut(&xyz)?;
+            // Stale entry for a tile we've since found another route to
+            if node.closed || node.g != g {
+                continue;
+            }
+            node.closed = true;
+            return Some((xyz, node.g, node.steps));
+        }
+        None
+    }
+
+    /// Walks back from the goal, filling in the tiles between jump points
+    fn path(&self) -> Vec<ByondXYZ> {
+        let mut path = Vec::new();
+        let mut current = self.goal;
+        while let Some(node) = self.nodes.get(&current) {
+            let Some(parent) = node.parent else {
+                break;
+            };
+            path.push(current);
+            if !node.via_link {
+                let (dx, dy) = (
+                    (parent.x() as i32 - current.x() as i32).signum(),
+                    (parent.y() as i32 - current.y() as i32).signum(),
+                );
+                let mut between = offset(current, dx, dy);
+                while between != parent {
+                    path.push(between);
+                    between = offset(between, dx, dy);
+                }
+            }
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// The direction we arrived at a tile from, if we walked there
+    fn arrival_direction(&self, xyz: ByondXYZ) -> Option<(i32, i32)> {
+        let node = self.nodes.get(&xyz)?;
+        if node.via_link {
+            return None;
+        }
+        let parent = node.parent?;
+        Some((
+            (xyz.x() as i32 - parent.x() as i32).signum(),
+            (xyz.y() as i32 - parent.y() as i32).signum(),
+        ))
+    }
+
+    /// The tiles worth jumping towards from a tile, skipping any that are better reached some other way
+    fn jps_neighbours(&mut self, xyz: ByondXYZ) -> Vec<(i32, i32)> {
+        let (x, y, z) = (xyz.x() as i32, xyz.y() as i32, xyz.z());
+        let mut neighbours = Vec::with_capacity(8);
+
+        let Some((dx, dy)) = self.arrival_direction(xyz) else {
+            for (dx, dy) in self.directions() {
+                if (*dx != 0 && *dy != 0) && !self.diagonal_allowed(xyz, *dx, *dy) {
+                    continue;
+                }
+                if self.walkable_at(x + dx, y + dy, z) {
+                    neighbours.push((x + dx, y + dy));
+                }
+            }
+            return neighbours;
+        };
+
+        match self.options.diagonals {
+            DiagonalMode::Never => {
+                if dx != 0 {
+                    neighbours.extend([(x, y - 1), (x, y + 1), (x + dx, y)]);
+                } else {
+                    neighbours.extend([(x - 1, y), (x + 1, y), (x, y + dy)]);
+                }
+            }
+            DiagonalMode::IfBothCardinalsOpen => {
+                if dx != 0 && dy != 0 {
+                    let vertical = self.walkable_at(x, y + dy, z);
+                    let horizontal = self.walkable_at(x + dx, y, z);
+                    neighbours.extend([(x, y + dy), (x + dx, y)]);
+                    if vertical && horizontal {
+                        neighbours.push((x + dx, y + dy));
+                    }
+                } else if dx != 0 {
+                    let next = self.walkable_at(x + dx, y, z);
+                    let top = self.walkable_at(x, y + 1, z);
+                    let bottom = self.walkable_at(x, y - 1, z);
+                    if next {
+                        neighbours.push((x + dx, y));
+                        if top {
+                            neighbours.push((x + dx, y + 1));
+                        }
+                        if bottom {
+                            neighbours.push((x + dx, y - 1));
+                        }
+                    }
+                    neighbours.extend([(x, y + 1), (x, y - 1)]);
+                } else {
+                    let next = self.walkable_at(x, y + dy, z);
+                    let right = self.walkable_at(x + 1, y, z);
+                    let left = self.walkable_at(x - 1, y, z);
+                    if next {
+                        neighbours.push((x, y + dy));
+                        if right {
+                            neighbours.push((x + 1, y + dy));
+                        }
+                        if left {
+                            neighbours.push((x - 1, y + dy));
+                        }
+                    }
+                    neighbours.extend([(x + 1, y), (x - 1, y)]);
+                }
+            }
+            DiagonalMode::Always | DiagonalMode::IfOneCardinalOpen => {
+                let one_open = self.options.diagonals == DiagonalMode::IfOneCardinalOpen;
+                if dx != 0 && dy != 0 {
+                    let vertical = self.walkable_at(x, y + dy, z);
+                    let horizontal = self.walkable_at(x + dx, y, z);
+                    neighbours.extend([(x, y + dy), (x + dx, y)]);
+                    if !one_open || vertical || horizontal {
+                        neighbours.push((x + dx, y + dy));
+                    }
+                    if !self.walkable_at(x - dx, y, z) && (!one_open || vertical) {
+                        neighbours.push((x - dx, y + dy));
+                    }
+                    if !self.walkable_at(x, y - dy, z) && (!one_open || horizontal) {
+                        neighbours.push((x + dx, y - dy));
+                    }
+                } else if dx != 0 {
+                    let next = self.walkable_at(x + dx, y, z);
+                    neighbours.push((x + dx, y));
+                    if !one_open || next {
+                        if !self.walkable_at(x, y + 1, z) {
+                            neighbours.push((x + dx, y + 1));
+                        }
+                        if !self.walkable_at(x, y - 1, z) {
+                            neighbours.push((x + dx, y - 1));
+                        }
+                    }
+                } else {
+                    let next = self.walkable_at(x, y + dy, z);
+                    neighbours.push((x, y + dy));
+                    if !one_open || next {
+                        if !self.walkable_at(x + 1, y, z) {
+                            neighbours.push((x + 1, y + dy));
+                        }
+                        if !self.walkable_at(x - 1, y, z) {
+                            neighbours.push((x - 1, y + dy));
+                        }
+                    }
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// Moves from a tile in a straight line until something interesting happens, returning where it stopped,
+    /// or [`None`] if it ran into something first.
+    fn jump(&mut self, mut x: i32, mut y: i32, z: i16, dx: i32, dy: i32) -> Option<(i32, i32)> {
+        loop {
+            if !self.walkable_at(x, y, z) {
+                return None;
+            }
+            let xyz = ByondXYZ::with_coords((x as i16, y as i16, z));
+            if xyz == self.goal || self.options.links.contains_key(&xyz) {
+                return Some((x, y));
+            }
+
+            let forced = match self.options.diagonals {
+                DiagonalMode::Never | DiagonalMode::IfBothCardinalsOpen => {
+                    if dx != 0 && dy != 0 {
+                        false
+                    } else if dx != 0 {
+                        (self.walkable_at(x, y - 1, z) && !self.walkable_at(x - dx, y - 1, z))
+                            || (self.walkable_at(x, y + 1, z)
+                                && !self.walkable_at(x - dx, y + 1, z))
+                    } else {
+                        (self.walkable_at(x - 1, y, z) && !self.walkable_at(x - 1, y - dy, z))
+                            || (self.walkable_at(x + 1, y, z)
+                                && !self.walkable_at(x + 1, y - dy, z))
+                    }
+                }
+                DiagonalMode::Always | DiagonalMode::IfOneCardinalOpen => {
+                    if dx != 0 && dy != 0 {
+                        (self.walkable_at(x - dx, y + dy, z) && !self.walkable_at(x - dx, y, z))
+                            || (self.walkable_at(x + dx, y - dy, z)
+                                && !self.walkable_at(x, y - dy, z))
+                    } else if dx != 0 {
+                        (self.walkable_at(x + dx, y + 1, z) && !self.walkable_at(x, y + 1, z))
+                            || (self.walkable_at(x + dx, y - 1, z)
+                                && !self.walkable_at(x, y - 1, z))
+                    } else {
+                        (self.walkable_at(x + 1, y + dy, z) && !self.walkable_at(x + 1, y, z))
+                            || (self.walkable_at(x - 1, y + dy, z)
+                                && !self.walkable_at(x - 1, y, z))
+                    }
+                }
+            };
+            if forced {
+                return Some((x, y));
+            }
+
+            let found_sideways = if dx != 0 && dy != 0 {
+                // Diagonal jumps stop wherever a straight jump would find something
+                self.jump(x + dx, y, z, dx, 0).is_some() || self.jump(x, y + dy, z, 0, dy).is_some()
+            } else if dy != 0 && self.options.diagonals == DiagonalMode::Never {
+                // Without diagonals, vertical jumps also stop wherever a horizontal one would find something
+                self.jump(x + 1, y, z, 1, 0).is_some() || self.jump(x - 1, y, z, -1, 0).is_some()
+            } else {
+                false
+            };
+            if found_sideways {
+                return Some((x, y));
+            }
+
+            let can_continue = match self.options.diagonals {
+                DiagonalMode::IfBothCardinalsOpen => {
+                    self.walkable_at(x + dx, y, z) && self.walkable_at(x, y + dy, z)
+                }
+                DiagonalMode::IfOneCardinalOpen => {
+                    self.walkable_at(x + dx, y, z) || self.walkable_at(x, y + dy, z)
+                }
+                _ => true,
+            };
+            if !can_continue {
+                return None;
+            }
+            x += dx;
+            y += dy;
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Read-compute-write passes over a region of turfs, with the compute part spread over every core.
+//!
+//! A [`RegionPipeline`] snapshots some state from every turf in a region on the main thread, runs a function on
+//! every cell in parallel with [rayon](https://docs.rs/rayon), then writes back whatever changed on the main thread.
+//! Atmospherics, liquids and fire spread all fit this shape.
+//! ```ignore
+//! #[derive(Clone, PartialEq)]
+//! struct Heat(f32);
+//!
+//! impl CellState for Heat {
+//!     fn read(turf: &ByondValue) -> Result<Self, Error> {
+//!         Ok(Heat(turf.read_number("heat")?))
+//!     }
+//!
+//!     fn write(&self, _old: &Self, turf: &mut ByondValue) -> Result<(), Error> {
+//!         turf.write_var("heat", &self.0.into())
+//!     }
+//! }
+//!
+//! let mut pipeline = RegionPipeline::<Heat>::new(corner1, corner2)?;
+//! pipeline.set_write_budget(Some(500));
+//! // Every tick
+//! pipeline.step(|cell| {
+//!     let around: f32 = cell.neighbours().map(|heat| heat.0).sum();
+//!     Heat((cell.state().0 + around) / 5.0)
+//! });
+//! pipeline.write_back()?;
+//! ```
+//!
+//! Only available with the `parallel` feature.
+use rayon::prelude::*;
+
+use super::{byond_block, world_corners, ByondXYZ, XYZRange};
+use crate::{prelude::ByondValue, Error};
+
+/// The state a [`RegionPipeline`] keeps for each turf
+pub trait CellState: Clone + PartialEq + Send + Sync {
+    /// Reads the state from a turf. Called on the main thread.
+    fn read(turf: &ByondValue) -> Result<Self, Error>;
+
+    /// Writes the state to a turf. `old` is what the turf's vars were last read or written as, so only the vars that
+    /// differ from it need writing. Called on the main thread.
+    fn write(&self, old: &Self, turf: &mut ByondValue) -> Result<(), Error>;
+}
+
+/// Where cells are in the flat arrays, in block order
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min: ByondXYZ,
+    max: ByondXYZ,
+    width: usize,
+    height: usize,
+}
+
+impl Bounds {
+    fn index(&self, xyz: ByondXYZ) -> Option<usize> {
+        if xyz.x() < self.min.x()
+            || xyz.y() < self.min.y()
+            || xyz.z() < self.min.z()
+            || xyz.x() > self.max.x()
+            || xyz.y() > self.max.y()
+            || xyz.z() > self.max.z()
+        {
+            return None;
+        }
+        let (dx, dy, dz) = xyz - self.min;
+        Some(dx as usize + dy as usize * self.width + dz as usize * self.width * self.height)
+    }
+
+    fn xyz(&self, index: usize) -> ByondXYZ {
+        let (x, y, z) = (
+            index % self.width,
+            index / self.width % self.height,
+            index / (self.width * self.height),
+        );
+        ByondXYZ::with_coords((
+            self.min.x() + x as i16,
+            self.min.y() + y as i16,
+            self.min.z() + z as i16,
+        ))
+    }
+}
+
+/// One cell as seen by [`RegionPipeline::step`], with read access to the state of every cell before the step
+pub struct CellView<'a, S> {
+    states: &'a [S],
+    bounds: &'a Bounds,
+    index: usize,
+}
+
+impl<'a, S> CellView<'a, S> {
+    pub fn xyz(&self) -> ByondXYZ {
+        self.bounds.xyz(self.index)
+    }
+
+    /// This cell's state before the step
+    pub fn state(&self) -> &'a S {
+        &self.states[self.index]
+    }
+
+    /// The state of the cell at `xyz` before the step, or [`None`] if it's outside the region
+    pub fn at(&self, xyz: ByondXYZ) -> Option<&'a S> {
+        self.bounds.index(xyz).map(|index| &self.states[index])
+    }
+
+    /// The state of the cell `(dx, dy, dz)` away from this one
+    pub fn offset(&self, (dx, dy, dz): (i16, i16, i16)) -> Option<&'a S> {
+        let (x, y, z) = self.xyz().coordinates();
+        let xyz = (x.checked_add(dx)?, y.checked_add(dy)?, z.checked_add(dz)?);
+        self.at(ByondXYZ::with_coords(xyz))
+    }
+
+    /// The states of the cells north, south, east and west of this one that are inside the region
+    pub fn neighbours(&self) -> impl Iterator<Item = &'a S> + '_ {
+        [(0, 1, 0), (0, -1, 0), (1, 0, 0), (-1, 0, 0)]
+            .into_iter()
+            .filter_map(|offset| self.offset(offset))
+    }
+}
+
+/// A region of turfs with some state for each, see the [module docs](self).
+///
+/// Turfs are snapshotted when it's made, so make a new one if the map's been resized.
+pub struct RegionPipeline<S> {
+    bounds: Bounds,
+    turfs: Vec<ByondValue>,
+    /// The latest state of each cell
+    current: Vec<S>,
+    /// Where steps put their results before they're swapped with `current`
+    next: Vec<S>,
+    /// What each turf's vars were last read or written as
+    synced: Vec<S>,
+    /// Cells whose state hasn't been written back yet, in block order starting from `cursor`
+    dirty: Vec<usize>,
+    /// The cell after the last one written back, where the next write back carries on from
+    cursor: usize,
+    write_budget: Option<usize>,
+}
+
+impl<S: CellState> RegionPipeline<S> {
+    /// Snapshots the state of every turf between the two corners. Must be called on the main thread.
+    ///
+    /// The corners are clamped to the map, so the region is empty if it's entirely off the map or the map has no
+    /// turfs.
+    pub fn new(corner1: ByondXYZ, corner2: ByondXYZ) -> Result<Self, Error> {
+        let range = XYZRange::new(corner1, corner2);
+        let bounds = match world_corners()? {
+            Some((world_min, world_max)) => {
+                let (min, max) = (range.min_corner(), range.max_corner());
+                let min = ByondXYZ::with_coords((
+                    min.x().max(world_min.x()),
+                    min.y().max(world_min.y()),
+                    min.z().max(world_min.z()),
+                ));
+                let max = ByondXYZ::with_coords((
+                    max.x().min(world_max.x()),
+                    max.y().min(world_max.y()),
+                    max.z().min(world_max.z()),
+                ));
+                (min.x() <= max.x() && min.y() <= max.y() && min.z() <= max.z()).then(|| Bounds {
+                    min,
+                    max,
+                    width: (max.x() - min.x()) as usize + 1,
+                    height: (max.y() - min.y()) as usize + 1,
+                })
+            }
+            None => None,
+        };
+        let Some(bounds) = bounds else {
+            let empty = Bounds {
+                min: ByondXYZ::with_coords((1, 1, 1)),
+                max: ByondXYZ::new(),
+                width: 0,
+                height: 0,
+            };
+            return Self::with_turfs(empty, Vec::new());
+        };
+
+        let turfs = byond_block(bounds.min, bounds.max)?;
+        let depth = (bounds.max.z() - bounds.min.z()) as usize + 1;
+        let expected = bounds.width * bounds.height * depth;
+        if turfs.len() != expected {
+            return Err(Error::UnexpectedBlockSize {
+                expected,
+                actual: turfs.len(),
+            });
+        }
+        Self::with_turfs(bounds, turfs)
+    }
+
+    fn with_turfs(bounds: Bounds, turfs: Vec<ByondValue>) -> Result<Self, Error> {
+        let states = turfs.iter().map(S::read).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            bounds,
+            turfs,
+            current: states.clone(),
+            next: states.clone(),
+            synced: states,
+            dirty: Vec::new(),
+            cursor: 0,
+            write_budget: None,
+        })
+    }
+
+    /// Reads every turf's state again, for when DM has changed it. Anything that hadn't been written back yet is
+    /// lost. Must be called on the main thread.
+    pub fn snapshot(&mut self) -> Result<(), Error> {
+        self.synced = self.turfs.iter().map(S::read).collect::<Result<_, _>>()?;
+        self.current.clone_from(&self.synced);
+        self.dirty.clear();
+        Ok(())
+    }
+
+    /// Works out every cell's new state from the states before the step, in parallel.
+    ///
+    /// Doesn't touch BYOND, so this can run on any thread.
+    pub fn step<F>(&mut self, f: F)
+    where
+        F: Fn(CellView<'_, S>) -> S + Sync,
+    {
+        let (states, bounds) = (&self.current, &self.bounds);
+        self.next
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, next)| {
+                *next = f(CellView {
+                    states,
+                    bounds,
+                    index,
+                })
+            });
+        std::mem::swap(&mut self.current, &mut self.next);
+
+        let (current, synced) = (&self.current, &self.synced);
+        self.dirty = (0..current.len())
+            .into_par_iter()
+            .filter(|&index| current[index] != synced[index])
+            .collect();
+        // Carry on from where the last write back stopped, so cells late in the region aren't starved by a budget
+        let start = self.dirty.partition_point(|&index| index < self.cursor);
+        self.dirty.rotate_left(start);
+    }
+
+    /// Sets the most cells [`RegionPipeline::write_back`] writes each call, unlimited if [`None`]
+    pub fn set_write_budget(&mut self, budget: Option<usize>) {
+        self.write_budget = budget;
+    }
+
+    /// Writes changed cells back to their turfs, up to the write budget, and returns how many were written. The rest
+    /// are written by later calls, even if there are steps in between. Must be called on the main thread.
+    ///
+    /// A cell that fails to write is tried again after the next step.
+    pub fn write_back(&mut self) -> Result<usize, Error> {
+        let count = self
+            .write_budget
+            .map_or(self.dirty.len(), |budget| budget.min(self.dirty.len()));
+        for (written, &index) in self.dirty[..count].iter().enumerate() {
+            let state = &self.current[index];
+            self.cursor = index + 1;
+            if let Err(e) = state.write(&self.synced[index], &mut self.turfs[index]) {
+                self.dirty.drain(..=written);
+                return Err(e);
+            }
+            self.synced[index] = state.clone();
+        }
+        self.dirty.drain(..count);
+        Ok(count)
+    }
+
+    /// How many changed cells are waiting to be written back
+    pub fn pending_writes(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// The latest state of the cell at `xyz`, whether it's been written back or not
+    pub fn state(&self, xyz: ByondXYZ) -> Option<&S> {
+        self.bounds.index(xyz).map(|index| &self.current[index])
+    }
+
+    pub fn turf_at(&self, xyz: ByondXYZ) -> Option<ByondValue> {
+        self.bounds.index(xyz).map(|index| self.turfs[index])
+    }
+}
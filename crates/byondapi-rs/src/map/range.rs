@@ -0,0 +1,96 @@
+//! Rust side equivalents of DM's [`range`](https://www.byond.com/docs/ref/#/proc/range) and
+//! [`orange`](https://www.byond.com/docs/ref/#/proc/orange), plus circular ranges.
+//!
+//! Everything here is clamped to the world's bounds and returns turfs in the same order as
+//! [`byond_block`], which is the order BYOND itself uses.
+use super::{byond_block, ByondXYZ, XYZRange};
+use crate::{byond_string, prelude::ByondValue, world::World, Error};
+
+/// Gets the corners of the square of turfs within `dist` of `center`, clamped to the world's bounds.
+/// Returns [`None`] if none of it is on the map.
+pub fn range_bounds(center: ByondXYZ, dist: u16) -> Result<Option<(ByondXYZ, ByondXYZ)>, Error> {
+    let world = World::new();
+    let (maxx, maxy, maxz) = (
+        world.maxx()? as i32,
+        world.maxy()? as i32,
+        world.maxz()? as i32,
+    );
+    let (x, y, z) = (center.x() as i32, center.y() as i32, center.z() as i32);
+    let dist = dist as i32;
+
+    let min = ((x - dist).max(1), (y - dist).max(1));
+    let max = ((x + dist).min(maxx), (y + dist).min(maxy));
+    if z < 1 || z > maxz || min.0 > max.0 || min.1 > max.1 {
+        return Ok(None);
+    }
+    Ok(Some((
+        ByondXYZ::with_coords((min.0 as i16, min.1 as i16, z as i16)),
+        ByondXYZ::with_coords((max.0 as i16, max.1 as i16, z as i16)),
+    )))
+}
+
+/// Gets every turf within `dist` of `center` along with its coordinates, in block order
+fn turfs_with_coords(center: ByondXYZ, dist: u16) -> Result<Vec<(ByondXYZ, ByondValue)>, Error> {
+    let Some((min, max)) = range_bounds(center, dist)? else {
+        return Ok(Vec::new());
+    };
+    let turfs = byond_block(min, max)?;
+    Ok(XYZRange::new(min, max).zip(turfs).collect())
+}
+
+/// Corresponds to `for(var/turf/T in range(dist, center))`, gets every turf within `dist` tiles of `center`.
+pub fn range_turfs(center: ByondXYZ, dist: u16) -> Result<Vec<ByondValue>, Error> {
+    match range_bounds(center, dist)? {
+        Some((min, max)) => byond_block(min, max),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Corresponds to `for(var/turf/T in orange(dist, center))`, gets every turf within `dist` tiles of `center`,
+/// except the center itself.
+pub fn orange_turfs(center: ByondXYZ, dist: u16) -> Result<Vec<ByondValue>, Error> {
+    Ok(turfs_with_coords(center, dist)?
+        .into_iter()
+        .filter(|(xyz, _)| *xyz != center)
+        .map(|(_, turf)| turf)
+        .collect())
+}
+
+/// Gets every turf within a circle of `radius` around `center`. Uses the same `dx² + dy² <= radius * (radius + 0.5)`
+/// check as the usual DM `circle_range` helper, which rounds the circle out a bit so it doesn't look spiky.
+pub fn circle_range_turfs(center: ByondXYZ, radius: u16) -> Result<Vec<ByondValue>, Error> {
+    let radius_squared = radius as f32 * (radius as f32 + 0.5);
+    Ok(turfs_with_coords(center, radius)?
+        .into_iter()
+        .filter(|(xyz, _)| {
+            let (dx, dy, _) = *xyz - center;
+            ((dx as i32 * dx as i32 + dy as i32 * dy as i32) as f32) <= radius_squared
+        })
+        .map(|(_, turf)| turf)
+        .collect())
+}
+
+/// Puts each turf in the result followed by everything in its contents
+fn with_contents(turfs: Vec<ByondValue>) -> Result<Vec<ByondValue>, Error> {
+    let mut atoms = Vec::with_capacity(turfs.len());
+    for turf in turfs {
+        atoms.push(turf);
+        atoms.extend(turf.read_list_id(byond_string!("contents"))?);
+    }
+    Ok(atoms)
+}
+
+/// Like [`range_turfs`], but every turf is followed by the atoms in its contents.
+pub fn range_atoms(center: ByondXYZ, dist: u16) -> Result<Vec<ByondValue>, Error> {
+    with_contents(range_turfs(center, dist)?)
+}
+
+/// Like [`orange_turfs`], but every turf is followed by the atoms in its contents.
+pub fn orange_atoms(center: ByondXYZ, dist: u16) -> Result<Vec<ByondValue>, Error> {
+    with_contents(orange_turfs(center, dist)?)
+}
+
+/// Like [`circle_range_turfs`], but every turf is followed by the atoms in its contents.
+pub fn circle_range_atoms(center: ByondXYZ, radius: u16) -> Result<Vec<ByondValue>, Error> {
+    with_contents(circle_range_turfs(center, radius)?)
+}
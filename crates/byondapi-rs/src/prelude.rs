@@ -28,6 +28,9 @@ pub use byondapi_sys::CByondValue as InternalByondValue;
 
 // As well as our own types.
 pub use crate::byond_string;
+pub use crate::main_thread::MainThread;
+pub use crate::value::pointer::ByondPointer;
 pub use crate::value::pointer::ByondValuePointer;
+pub use crate::value::ref_id::RefId;
 pub use crate::value::types::ValueType;
 pub use crate::value::ByondValue;
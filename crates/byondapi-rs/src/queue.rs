@@ -0,0 +1,285 @@
+//! Sending messages from worker threads to handlers on the main thread, in batches instead of one
+//! [`thread_sync_blocking`](crate::threadsync::thread_sync_blocking) each.
+//!
+//! Messages pile up in a [`MainThreadQueue`] until it's drained on the main thread, which runs the queue's handler
+//! on each of them. Queues are drained by calling the generated `byondapi_drain_queues()` proc once a tick from DM,
+//! which drains every queue within its [`DrainBudget`], or by turning on [`MainThreadQueue::set_auto_drain`], which
+//! drains a budget's worth whenever messages come in and leaves the rest for the next tick.
+//! ```ignore
+//! let queue = MainThreadQueue::new(1024, |(mob, damage): (RemoteValue, f32)| {
+//!     mob.value().call("take_damage", &[damage.into()]).map(|_| ())
+//! });
+//! let sender = queue.clone();
+//! std::thread::spawn(move || sender.push((mob, 10.0)));
+//! ```
+//!
+//! Requires the generated `bindings.dm` to be included in your DM project.
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, TryLockError, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    byond_string, error::report_error, global_call::call_global_id, prelude::ByondValue,
+    threadsync::thread_sync_deferred, Error,
+};
+
+inventory::submit! {
+    crate::binds::DmCode(r#"
+/proc/byondapi_drain_next_tick()
+	set waitfor = FALSE
+	sleep(world.tick_lag)
+	byondapi_drain_waiting_queues()
+"#)
+}
+
+type Handler<T> = Box<dyn FnMut(T) -> Result<(), Error> + Send>;
+
+/// How much of a queue to handle each time it's drained
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrainBudget {
+    /// The most messages to handle, unlimited if [`None`]
+    pub max_messages: Option<usize>,
+    /// How long to keep handling messages for, unlimited if [`None`]. At least one message is always handled.
+    pub max_time: Option<Duration>,
+}
+
+struct Shared<T> {
+    messages: Mutex<VecDeque<T>>,
+    /// Signalled whenever a message is taken out, for pushes waiting on room
+    space: Condvar,
+    capacity: usize,
+    handler: Mutex<Handler<T>>,
+    budget: Mutex<DrainBudget>,
+    auto_drain: AtomicBool,
+    drain_scheduled: AtomicBool,
+}
+
+impl<T: Send + 'static> Shared<T> {
+    fn drain(&self) -> Result<usize, Error> {
+        let budget = *self.budget.lock().unwrap();
+        let mut handler = match self.handler.try_lock() {
+            Ok(handler) => handler,
+            // Already being drained further up the stack, a handler must have called back into DM
+            Err(TryLockError::WouldBlock) => return Ok(0),
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        };
+
+        let started = Instant::now();
+        let mut handled = 0;
+        while budget.max_messages.is_none_or(|max| handled < max)
+            && (handled == 0 || budget.max_time.is_none_or(|max| started.elapsed() < max))
+        {
+            let Some(message) = self.messages.lock().unwrap().pop_front() else {
+                break;
+            };
+            self.space.notify_one();
+            handled += 1;
+            handler(message)?;
+        }
+        Ok(handled)
+    }
+
+    fn schedule_drain(self: &Arc<Self>) {
+        if self.drain_scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let shared = self.clone();
+        thread_sync_deferred(move || shared.scheduled_drain());
+    }
+
+    /// Runs a drain queued by [`Shared::schedule_drain`], on the main thread
+    fn scheduled_drain(self: Arc<Self>) {
+        if let Err(e) = self.drain() {
+            report_error(e);
+        }
+        self.drain_scheduled.store(false, Ordering::Release);
+        if !self.auto_drain.load(Ordering::Acquire)
+            || self.messages.lock().unwrap().is_empty()
+            || self.drain_scheduled.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        // Whatever's left waits for the next tick, so the budget holds and this doesn't recurse, since thread_sync
+        // runs straight away on the main thread
+        WAITING.lock().unwrap().push(self);
+        if let Err(e) = call_global_id(byond_string!("byondapi_drain_next_tick"), &[]) {
+            report_error(e);
+        }
+    }
+}
+
+/// Lets every kind of queue sit in the same registry
+trait Drain: Send + Sync {
+    fn drain(&self) -> Result<usize, Error>;
+
+    fn scheduled_drain(self: Arc<Self>);
+}
+
+impl<T: Send + 'static> Drain for Shared<T> {
+    fn drain(&self) -> Result<usize, Error> {
+        Shared::drain(self)
+    }
+
+    fn scheduled_drain(self: Arc<Self>) {
+        Shared::scheduled_drain(self)
+    }
+}
+
+/// Every queue that's still alive, for `byondapi_drain_queues()`
+static QUEUES: Mutex<Vec<Weak<dyn Drain>>> = Mutex::new(Vec::new());
+
+/// Auto draining queues with messages left over, kept alive until `byondapi_drain_next_tick()` drains them
+static WAITING: Mutex<Vec<Arc<dyn Drain>>> = Mutex::new(Vec::new());
+
+/// A bounded queue of messages for the main thread, see the [module docs](self).
+///
+/// Clones share the same queue. It's unregistered once every clone has been dropped.
+pub struct MainThreadQueue<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for MainThreadQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> MainThreadQueue<T> {
+    /// Creates a queue holding up to `capacity` messages, at least one, each of which is passed to `handler` on the
+    /// main thread when it's drained.
+    pub fn new<F>(capacity: usize, handler: F) -> Self
+    where
+        F: FnMut(T) -> Result<(), Error> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            messages: Mutex::new(VecDeque::new()),
+            space: Condvar::new(),
+            capacity: capacity.max(1),
+            handler: Mutex::new(Box::new(handler)),
+            budget: Mutex::new(DrainBudget::default()),
+            auto_drain: AtomicBool::new(false),
+            drain_scheduled: AtomicBool::new(false),
+        });
+        let mut queues = QUEUES.lock().unwrap();
+        queues.retain(|queue| queue.strong_count() > 0);
+        queues.push(Arc::downgrade(&shared) as Weak<dyn Drain>);
+        Self { shared }
+    }
+
+    /// Adds a message to the queue, or gives it back if the queue is full.
+    pub fn try_push(&self, message: T) -> Result<(), T> {
+        let mut messages = self.shared.messages.lock().unwrap();
+        if messages.len() >= self.shared.capacity {
+            return Err(message);
+        }
+        messages.push_back(message);
+        drop(messages);
+        self.after_push();
+        Ok(())
+    }
+
+    /// Adds a message to the queue, waiting for room if it's full.
+    ///
+    /// Never call this on the main thread, the queue can't be drained while it's blocked.
+    pub fn push(&self, message: T) {
+        let mut messages = self.shared.messages.lock().unwrap();
+        while messages.len() >= self.shared.capacity {
+            messages = self.shared.space.wait(messages).unwrap();
+        }
+        messages.push_back(message);
+        drop(messages);
+        self.after_push();
+    }
+
+    fn after_push(&self) {
+        if self.shared.auto_drain.load(Ordering::Acquire) {
+            self.shared.schedule_drain();
+        }
+    }
+
+    /// Handles queued messages within the queue's budget, returning how many were handled. Must be called on the
+    /// main thread.
+    ///
+    /// Stops at the first error the handler returns, leaving the rest of the messages queued.
+    pub fn drain(&self) -> Result<usize, Error> {
+        self.shared.drain()
+    }
+
+    /// Sets how much is handled each time the queue is drained. Unlimited by default.
+    pub fn set_budget(&self, budget: DrainBudget) {
+        *self.shared.budget.lock().unwrap() = budget;
+    }
+
+    /// When on, pushing a message queues a drain through [`thread_sync_deferred`], so DM doesn't have to
+    /// drain the queue itself. Each drain stays within the budget, and anything left over is drained the next
+    /// tick. Errors from the handler are then reported through `stack_trace`.
+    pub fn set_auto_drain(&self, auto_drain: bool) {
+        self.shared.auto_drain.store(auto_drain, Ordering::Release);
+        if auto_drain && !self.is_empty() {
+            self.shared.schedule_drain();
+        }
+    }
+
+    /// How many messages are waiting to be handled
+    pub fn len(&self) -> usize {
+        self.shared.messages.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The most messages the queue can hold
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+/// Drains every queue within its budget, returning how many messages were handled in total. Must be called on the
+/// main thread.
+///
+/// Every queue gets drained even if some fail, the first error is returned.
+pub fn drain_all() -> Result<usize, Error> {
+    // Handlers might create queues, so they can't run with the registry locked
+    let queues: Vec<Arc<dyn Drain>> = QUEUES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    let mut handled = 0;
+    let mut first_error = None;
+    for queue in queues {
+        match queue.drain() {
+            Ok(count) => handled += count,
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(handled),
+    }
+}
+
+#[crate::bind]
+fn byondapi_drain_queues() -> Result<ByondValue, Error> {
+    Ok(ByondValue::new_num(drain_all()? as f32))
+}
+
+#[crate::bind]
+fn byondapi_drain_waiting_queues() -> Result<ByondValue, Error> {
+    let waiting = std::mem::take(&mut *WAITING.lock().unwrap());
+    for queue in waiting {
+        queue.scheduled_drain();
+    }
+    Ok(ByondValue::null())
+}
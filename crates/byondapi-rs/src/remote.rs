@@ -0,0 +1,345 @@
+//! Handles to BYOND values that other threads can hold and use.
+//!
+//! A [`RemoteValue`] keeps a reference to its value for as long as it's alive, and every operation on it is run
+//! on the main thread through [`thread_sync_deferred`]. Each operation has a blocking version, and a `_deferred`
+//! version that returns a [`ThreadSyncHandle`] instead of waiting. Operations on the main thread run straight away,
+//! since waiting there for the main thread to be free would never end.
+//! ```ignore
+//! std::thread::spawn(move || {
+//!     let health: f32 = mob.read_var("health")?;
+//!     mob.write_var("health", health - 10.0)?;
+//!     let held: RemoteValue = mob.call("get_active_hand", &[])?;
+//! });
+//! ```
+use std::sync::Arc;
+
+use crate::{
+    main_thread::MainThread,
+    prelude::ByondValue,
+    threadsync::{run_caught, thread_sync_deferred, ThreadSyncHandle},
+    Error,
+};
+
+/// Releases the reference held by a [`RemoteValue`] when the last clone of it goes away
+struct Held(ByondValue);
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        let mut value = self.0;
+        thread_sync_deferred(move || value.decrement_ref());
+    }
+}
+
+/// A value that can be sent to and used from any thread, see the [module docs](self).
+///
+/// Clones share the same reference, which is only released once all of them are dropped.
+#[derive(Clone)]
+pub struct RemoteValue(Arc<Held>);
+
+impl RemoteValue {
+    /// Wraps a value, holding a reference to it. Must be called on the main thread.
+    pub fn new(mut value: ByondValue) -> Self {
+        value.increment_ref();
+        Self(Arc::new(Held(value)))
+    }
+
+    /// The value this holds. It stays valid for as long as this does, but can only be used on the main thread.
+    pub fn value(&self) -> ByondValue {
+        self.0 .0
+    }
+
+    /// Runs `f` with the value on the main thread, without waiting for it. If this is the main thread, `f` runs
+    /// straight away. What `f` returns has to be [`RemoteSafe`], so values can't leave the main thread through it.
+    pub fn with_deferred<F, R>(&self, f: F) -> ThreadSyncHandle<R>
+    where
+        F: FnOnce(ByondValue) -> R + Send + 'static,
+        R: RemoteSafe,
+    {
+        if MainThread::current().is_some() {
+            return ThreadSyncHandle::ready(run_caught(|| f(self.value())));
+        }
+        // Moving a clone in keeps the value alive until f has run
+        let this = self.clone();
+        thread_sync_deferred(move || f(this.value()))
+    }
+
+    /// Runs `f` with the value on the main thread, and returns its result.
+    pub fn with<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(ByondValue) -> Result<R, Error> + Send + 'static,
+        R: RemoteSafe,
+    {
+        self.with_deferred(f).wait()?
+    }
+
+    /// Like [`RemoteValue::read_var`], without waiting
+    pub fn read_var_deferred<R>(&self, name: &str) -> ThreadSyncHandle<Result<R, Error>>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+    {
+        let name = name.to_owned();
+        self.with_deferred(move |value| Ok(R::try_from(value.read_var(name)?)?))
+    }
+
+    /// Reads a var, converting it to `R`. Use `R = RemoteValue` to get a handle to the var's value.
+    pub fn read_var<R>(&self, name: &str) -> Result<R, Error>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+    {
+        self.read_var_deferred(name).wait()?
+    }
+
+    /// Like [`RemoteValue::write_var`], without waiting
+    pub fn write_var_deferred<V: Into<RemoteArg>>(
+        &self,
+        name: &str,
+        new_value: V,
+    ) -> ThreadSyncHandle<Result<(), Error>> {
+        let name = name.to_owned();
+        let new_value = new_value.into();
+        self.with_deferred(move |mut value| value.write_var(name, &new_value.into_value()?))
+    }
+
+    /// Sets a var to `new_value`
+    pub fn write_var<V: Into<RemoteArg>>(&self, name: &str, new_value: V) -> Result<(), Error> {
+        self.write_var_deferred(name, new_value).wait()?
+    }
+
+    /// Like [`RemoteValue::call`], without waiting
+    pub fn call_deferred<R>(
+        &self,
+        name: &str,
+        args: &[RemoteArg],
+    ) -> ThreadSyncHandle<Result<R, Error>>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+    {
+        let name = name.to_owned();
+        let args = args.to_vec();
+        self.with_deferred(move |value| {
+            let args = args
+                .into_iter()
+                .map(RemoteArg::into_value)
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(R::try_from(value.call(name, &args)?)?)
+        })
+    }
+
+    /// Calls a proc on the value, converting what it returns to `R`. Like [`ByondValue::call`], this doesn't wait
+    /// for procs that sleep.
+    pub fn call<R>(&self, name: &str, args: &[RemoteArg]) -> Result<R, Error>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+    {
+        self.call_deferred(name, args).wait()?
+    }
+
+    /// Like [`RemoteValue::read_list_index`], without waiting
+    pub fn read_list_index_deferred<R, I>(&self, index: I) -> ThreadSyncHandle<Result<R, Error>>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+        I: Into<RemoteArg>,
+    {
+        let index = index.into();
+        self.with_deferred(move |value| {
+            Ok(R::try_from(
+                value.read_list_index_internal(&index.into_value()?)?,
+            )?)
+        })
+    }
+
+    /// Reads `list[index]`, converting it to `R`
+    pub fn read_list_index<R, I>(&self, index: I) -> Result<R, Error>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+        I: Into<RemoteArg>,
+    {
+        self.read_list_index_deferred(index).wait()?
+    }
+
+    /// Like [`RemoteValue::write_list_index`], without waiting
+    pub fn write_list_index_deferred<I, V>(
+        &self,
+        index: I,
+        new_value: V,
+    ) -> ThreadSyncHandle<Result<(), Error>>
+    where
+        I: Into<RemoteArg>,
+        V: Into<RemoteArg>,
+    {
+        let (index, new_value) = (index.into(), new_value.into());
+        self.with_deferred(move |mut value| {
+            value.write_list_index_internal(&index.into_value()?, &new_value.into_value()?)
+        })
+    }
+
+    /// Sets `list[index] = new_value`
+    pub fn write_list_index<I, V>(&self, index: I, new_value: V) -> Result<(), Error>
+    where
+        I: Into<RemoteArg>,
+        V: Into<RemoteArg>,
+    {
+        self.write_list_index_deferred(index, new_value).wait()?
+    }
+
+    /// Like [`RemoteValue::get_list`], without waiting
+    pub fn get_list_deferred<R>(&self) -> ThreadSyncHandle<Result<Vec<R>, Error>>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+    {
+        self.with_deferred(|value| {
+            value
+                .get_list_values()?
+                .into_iter()
+                .map(|item| Ok(R::try_from(item)?))
+                .collect()
+        })
+    }
+
+    /// Gets every item in the list, converting each of them to `R`
+    pub fn get_list<R>(&self) -> Result<Vec<R>, Error>
+    where
+        R: TryFrom<ByondValue> + RemoteSafe,
+        Error: From<R::Error>,
+    {
+        self.get_list_deferred().wait()?
+    }
+}
+
+/// Only valid on the main thread, like [`RemoteValue::new`]
+impl TryFrom<ByondValue> for RemoteValue {
+    type Error = Error;
+
+    fn try_from(value: ByondValue) -> Result<Self, Self::Error> {
+        Ok(Self::new(value))
+    }
+}
+
+/// Something that can be handed from the main thread to any other, which a [`ByondValue`] can't be since it's only
+/// kept alive while the main thread is using it. Wrap values in a [`RemoteValue`] to send them instead.
+///
+/// Implement it for your own types if they don't hold any [`ByondValue`]s.
+/// ```compile_fail
+/// # use byondapi::{prelude::*, remote::RemoteValue};
+/// fn leak(remote: RemoteValue) {
+///     let _ = remote.with(|value| value.read_var("name"));
+/// }
+/// ```
+pub trait RemoteSafe: Send + 'static {}
+
+macro_rules! remote_safe {
+    ($($ty:ty),*) => {
+        $(impl RemoteSafe for $ty {})*
+    };
+}
+
+remote_safe!(
+    (),
+    bool,
+    char,
+    f32,
+    f64,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    String,
+    RemoteValue,
+    RemoteArg
+);
+
+impl<T: RemoteSafe> RemoteSafe for Vec<T> {}
+impl<T: RemoteSafe> RemoteSafe for Option<T> {}
+impl<T: RemoteSafe> RemoteSafe for Box<T> {}
+impl<T: RemoteSafe> RemoteSafe for Result<T, Error> {}
+impl<A: RemoteSafe, B: RemoteSafe> RemoteSafe for (A, B) {}
+impl<A: RemoteSafe, B: RemoteSafe, C: RemoteSafe> RemoteSafe for (A, B, C) {}
+
+/// Something to pass to a [`RemoteValue`] operation, which only becomes a [`ByondValue`] on the main thread.
+#[derive(Clone)]
+pub enum RemoteArg {
+    Null,
+    Number(f32),
+    String(String),
+    Remote(RemoteValue),
+}
+
+impl RemoteArg {
+    /// Copies what can be copied out of a value, and holds a reference to anything else. Must be called on the main
+    /// thread.
+    pub(crate) fn from_value(value: ByondValue) -> Result<Self, Error> {
+        Ok(if value.is_null() {
+            RemoteArg::Null
+        } else if value.is_num() {
+            RemoteArg::Number(value.get_number()?)
+        } else if value.is_str() {
+            RemoteArg::String(value.get_string()?)
+        } else {
+            RemoteArg::Remote(RemoteValue::new(value))
+        })
+    }
+
+    pub(crate) fn into_value(self) -> Result<ByondValue, Error> {
+        Ok(match self {
+            RemoteArg::Null => ByondValue::null(),
+            RemoteArg::Number(number) => ByondValue::new_num(number),
+            RemoteArg::String(string) => ByondValue::new_str(string)?,
+            RemoteArg::Remote(remote) => remote.value(),
+        })
+    }
+}
+
+impl From<()> for RemoteArg {
+    fn from(_: ()) -> Self {
+        RemoteArg::Null
+    }
+}
+
+impl From<f32> for RemoteArg {
+    fn from(number: f32) -> Self {
+        RemoteArg::Number(number)
+    }
+}
+
+impl From<bool> for RemoteArg {
+    fn from(boolean: bool) -> Self {
+        RemoteArg::Number(if boolean { 1.0 } else { 0.0 })
+    }
+}
+
+impl From<&str> for RemoteArg {
+    fn from(string: &str) -> Self {
+        RemoteArg::String(string.to_owned())
+    }
+}
+
+impl From<String> for RemoteArg {
+    fn from(string: String) -> Self {
+        RemoteArg::String(string)
+    }
+}
+
+impl From<RemoteValue> for RemoteArg {
+    fn from(remote: RemoteValue) -> Self {
+        RemoteArg::Remote(remote)
+    }
+}
+
+impl From<&RemoteValue> for RemoteArg {
+    fn from(remote: &RemoteValue) -> Self {
+        RemoteArg::Remote(remote.clone())
+    }
+}
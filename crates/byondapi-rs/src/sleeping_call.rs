@@ -0,0 +1,216 @@
+//! Calling procs that may sleep, and getting their return value once they actually return.
+//!
+//! [`ByondValue::call`] and [`crate::global_call::call_global`] are implicitly `waitfor=0`, so a proc that sleeps hands
+//! back null instead of its result. The functions here instead run the proc through a generated DM wrapper,
+//! which hands the result back to rust when the proc finally returns.
+//!
+//! Values handed back this way have a reference held for them, so they stay valid however long they're kept. Call
+//! [`ByondValue::decrement_ref`] on them once they're no longer needed.
+//!
+//! Requires the generated `bindings.dm` to be included in your DM project.
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use crate::{byond_string, global_call::call_global_id, prelude::*, Error};
+
+type SleepingCallback = Box<dyn FnOnce(Result<ByondValue, Error>) + Send>;
+
+/// Callbacks waiting on a proc to return, keyed by request id
+static PENDING: Mutex<BTreeMap<u32, SleepingCallback>> = Mutex::new(BTreeMap::new());
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+inventory::submit! {
+    crate::binds::DmCode(r#"
+/datum/byondapi_sleeping_guard
+	var/id
+
+/datum/byondapi_sleeping_guard/New(id)
+	src.id = id
+
+// Collected when the call below ends however it ends, so calls that are killed don't wait forever
+/datum/byondapi_sleeping_guard/Del()
+	byondapi_sleeping_call_failed(id, "killed before returning")
+	return ..()
+
+/proc/byondapi_call_sleeping(id, target, proc_name, list/arguments)
+	set waitfor = FALSE
+	var/datum/byondapi_sleeping_guard/guard = new(id)
+	var/result
+	try
+		if(isnull(target))
+			result = call(text2path("/proc/[proc_name]"))(arglist(arguments))
+		else
+			result = call(target, proc_name)(arglist(arguments))
+	catch(var/exception/error)
+		byondapi_sleeping_call_failed(id, "[error.name]")
+		return
+	byondapi_sleeping_call_returned(id, result)
+"#)
+}
+
+/// Registers a callback under an id that isn't already waiting on a call
+fn register(callback: SleepingCallback) -> u32 {
+    let mut pending = PENDING.lock().unwrap();
+    loop {
+        // Ids are sent to DM as numbers, so they have to stay within the range a float can represent exactly
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed) % (1 << 24);
+        if let Entry::Vacant(entry) = pending.entry(id) {
+            entry.insert(callback);
+            return id;
+        }
+    }
+}
+
+fn start_call<T: Into<Vec<u8>>>(
+    target: ByondValue,
+    name: T,
+    args: &[ByondValue],
+    callback: SleepingCallback,
+) -> Result<(), Error> {
+    let proc_name = ByondValue::new_str(name)?;
+    let arguments = ByondValue::try_from(args)?;
+
+    let id = register(callback);
+
+    // The lock must not be held here, a proc that doesn't sleep will call back into us before this returns
+    if let Err(e) = call_global_id(
+        byond_string!("byondapi_call_sleeping"),
+        &[(id as f32).into(), target, proc_name, arguments],
+    ) {
+        PENDING.lock().unwrap().remove(&id);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn finish_call(id: ByondValue, result: Result<ByondValue, Error>) -> Result<ByondValue, Error> {
+    let id = id.get_number()? as u32;
+    let callback = PENDING.lock().unwrap().remove(&id);
+    if let Some(callback) = callback {
+        // The argument is only good for this call, and the result is usually kept for longer
+        callback(result.map(|mut value| {
+            value.increment_ref();
+            value
+        }));
+    }
+    Ok(ByondValue::null())
+}
+
+#[crate::bind]
+fn byondapi_sleeping_call_returned(
+    id: ByondValue,
+    result: ByondValue,
+) -> Result<ByondValue, Error> {
+    finish_call(id, Ok(result))
+}
+
+#[crate::bind]
+fn byondapi_sleeping_call_failed(id: ByondValue, error: ByondValue) -> Result<ByondValue, Error> {
+    let error = error.get_string()?;
+    finish_call(id, Err(Error::ProcRuntime(error)))
+}
+
+impl ByondValue {
+    /// Call a proc using self as src, and run `callback` with its return value once it returns, even if it sleeps.
+    ///
+    /// If the proc doesn't sleep, `callback` runs before this returns.
+    /// If the proc runtimes or is killed before it returns, `callback` gets an [`Error::ProcRuntime`].
+    pub fn call_sleeping<T, F>(
+        &self,
+        name: T,
+        args: &[ByondValue],
+        callback: F,
+    ) -> Result<(), Error>
+    where
+        T: Into<Vec<u8>>,
+        F: FnOnce(Result<ByondValue, Error>) + Send + 'static,
+    {
+        start_call(*self, name, args, Box::new(callback))
+    }
+
+    /// Call a proc using self as src, returning a [`Future`] that resolves to its return value once it returns,
+    /// even if it sleeps.
+    pub fn call_future<T: Into<Vec<u8>>>(
+        &self,
+        name: T,
+        args: &[ByondValue],
+    ) -> Result<ProcFuture, Error> {
+        let (future, callback) = ProcFuture::new();
+        start_call(*self, name, args, callback)?;
+        Ok(future)
+    }
+}
+
+/// Calls a global proc, and runs `callback` with its return value once it returns, even if it sleeps.
+///
+/// If the proc doesn't sleep, `callback` runs before this returns.
+/// If the proc runtimes or is killed before it returns, `callback` gets an [`Error::ProcRuntime`].
+pub fn call_global_sleeping<T, F>(name: T, args: &[ByondValue], callback: F) -> Result<(), Error>
+where
+    T: Into<Vec<u8>>,
+    F: FnOnce(Result<ByondValue, Error>) + Send + 'static,
+{
+    start_call(ByondValue::null(), name, args, Box::new(callback))
+}
+
+/// Calls a global proc, returning a [`Future`] that resolves to its return value once it returns, even if it sleeps.
+pub fn call_global_future<T: Into<Vec<u8>>>(
+    name: T,
+    args: &[ByondValue],
+) -> Result<ProcFuture, Error> {
+    let (future, callback) = ProcFuture::new();
+    start_call(ByondValue::null(), name, args, callback)?;
+    Ok(future)
+}
+
+#[derive(Default)]
+struct ProcFutureState {
+    result: Option<Result<ByondValue, Error>>,
+    waker: Option<Waker>,
+}
+
+/// A proc call started by [`ByondValue::call_future`] or [`call_global_future`].
+///
+/// The result is only ever delivered by BYOND ticking, so make sure whatever polls this doesn't block the main thread.
+pub struct ProcFuture {
+    state: Arc<Mutex<ProcFutureState>>,
+}
+
+impl ProcFuture {
+    fn new() -> (Self, SleepingCallback) {
+        let state = Arc::new(Mutex::new(ProcFutureState::default()));
+        let callback_state = state.clone();
+        let callback = Box::new(move |result| {
+            let mut state = callback_state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        (Self { state }, callback)
+    }
+}
+
+impl Future for ProcFuture {
+    type Output = Result<ByondValue, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
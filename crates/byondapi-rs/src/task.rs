@@ -0,0 +1,207 @@
+//! Running futures on BYOND's main thread, for async code that needs to touch game state.
+//!
+//! [`spawn_main`] polls a future only ever on the main thread. Each time it's woken polling goes through
+//! [`thread_sync_deferred`], which polls straight away if it's woken on the main thread and queues it otherwise.
+//! Async code running elsewhere, like in a tokio runtime, can use [`main_thread`] to run a closure on the main thread
+//! and await its result. That's the way to hop onto the main thread, since a future can't move itself between
+//! threads when another runtime is polling it: whatever needs the main thread goes in the closure.
+//! ```ignore
+//! tokio::spawn(async move {
+//!     let response = reqwest::get(url).await?.text().await?;
+//!     main_thread(move || mob.write_var("motd", &ByondValue::new_str(response)?)).await??;
+//! });
+//! ```
+use std::{
+    future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use crate::{threadsync::thread_sync_deferred, Error};
+
+type BoxedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Not queued, waiting to be woken
+const IDLE: u8 = 0;
+/// Queued to be polled on the main thread
+const SCHEDULED: u8 = 1;
+/// Being polled right now
+const RUNNING: u8 = 2;
+/// Woken while being polled, so it needs queueing again afterwards
+const RUNNING_WOKEN: u8 = 3;
+/// Finished, nothing left to poll
+const DONE: u8 = 4;
+
+struct Task {
+    future: Mutex<Option<BoxedFuture>>,
+    state: AtomicU8,
+}
+
+impl Task {
+    fn schedule(self: Arc<Self>) {
+        thread_sync_deferred(move || self.run());
+    }
+
+    /// Polls the future once, on the main thread
+    fn run(self: Arc<Self>) {
+        self.state.store(RUNNING, Ordering::Release);
+        let waker = Waker::from(self.clone());
+        let mut future = self.future.lock().unwrap();
+        let Some(inner) = future.as_mut() else {
+            self.state.store(DONE, Ordering::Release);
+            return;
+        };
+        if inner
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_ready()
+        {
+            *future = None;
+            self.state.store(DONE, Ordering::Release);
+            return;
+        }
+        drop(future);
+
+        if self
+            .state
+            .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // Woken while running
+            self.state.store(SCHEDULED, Ordering::Release);
+            self.schedule();
+        }
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            let next = match state {
+                IDLE => SCHEDULED,
+                RUNNING => RUNNING_WOKEN,
+                _ => return,
+            };
+            match self
+                .state
+                .compare_exchange(state, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => break,
+                Err(actual) => state = actual,
+            }
+        }
+        if state == IDLE {
+            self.schedule();
+        }
+    }
+}
+
+/// Polls a future, catching panics so they don't unwind into BYOND
+struct CatchUnwind<F> {
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self.inner.as_mut();
+        match catch_unwind(AssertUnwindSafe(|| inner.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(Error::CallbackPanicked(
+                crate::threadsync::panic_message(payload),
+            ))),
+        }
+    }
+}
+
+struct JoinState<T> {
+    result: Option<Result<T, Error>>,
+    waker: Option<Waker>,
+    finished: bool,
+}
+
+/// A future started by [`spawn_main`] or [`main_thread`], which resolves to its output.
+///
+/// Dropping this doesn't stop the future, it just means nobody gets the output.
+/// Panics in the future are caught, and come back as [`Error::CallbackPanicked`].
+pub struct JoinHandle<T> {
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Checks if the future has finished
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Runs a future on the main thread, polling it whenever it's woken.
+///
+/// Can be called from any thread. On the main thread the first poll happens straight away, before this returns,
+/// anywhere else it's queued.
+pub fn spawn_main<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let state = Arc::new(Mutex::new(JoinState {
+        result: None,
+        waker: None,
+        finished: false,
+    }));
+    let task_state = state.clone();
+    let caught = CatchUnwind {
+        inner: Box::pin(future),
+    };
+    let task = Arc::new(Task {
+        future: Mutex::new(Some(Box::pin(async move {
+            let result = caught.await;
+            let mut state = task_state.lock().unwrap();
+            state.result = Some(result);
+            state.finished = true;
+            let waker = state.waker.take();
+            // Waking on the main thread polls whatever's awaiting this straight away, which locks the state again
+            drop(state);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }))),
+        state: AtomicU8::new(SCHEDULED),
+    });
+    task.schedule();
+    JoinHandle { state }
+}
+
+/// Runs `callback` on the main thread, returning a future that resolves to its result.
+///
+/// Unlike [`crate::threadsync::thread_sync_blocking`], awaiting this doesn't block the thread it's awaited on.
+pub fn main_thread<F, R>(callback: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    spawn_main(async move { callback() })
+}
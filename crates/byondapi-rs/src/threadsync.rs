@@ -1,25 +1,190 @@
+//! Running code on BYOND's main thread from other threads.
+//!
+//! Anything touching BYOND has to happen on the main thread, these functions hand a closure over to it.
+//! Panics in the closure are caught and returned as [`Error::CallbackPanicked`] instead of unwinding into BYOND.
+use crate::main_thread::MainThread;
 use crate::static_global::byond;
 use crate::value::ByondValue;
+use crate::Error;
 use byondapi_sys::CByondValue;
-use std::os::raw::c_void;
+use std::{
+    any::Any,
+    os::raw::c_void,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::mpsc::{self, Receiver, SyncSender},
+    time::Duration,
+};
 
-struct CallbackData<F: FnOnce() -> ByondValue + Send> {
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+/// Runs `callback`, catching panics
+pub(crate) fn run_caught<F: FnOnce() -> R, R>(callback: F) -> Result<R, Error> {
+    catch_unwind(AssertUnwindSafe(callback))
+        .map_err(|payload| Error::CallbackPanicked(panic_message(payload)))
+}
+
+struct BlockingData<F> {
     callback: Option<F>,
+    /// Only the error is kept here, the value goes back through BYOND
+    result: Result<(), Error>,
 }
 
-extern "C" fn trampoline<F: FnOnce() -> ByondValue + Send>(data: *mut c_void) -> CByondValue {
-    let data = unsafe { Box::from_raw(data as *mut CallbackData<F>) };
-    (data.callback.unwrap())().into_inner()
+extern "C" fn blocking_trampoline<F, R>(data: *mut c_void) -> CByondValue
+where
+    F: FnOnce() -> R + Send,
+    R: TryInto<ByondValue>,
+    Error: From<R::Error>,
+{
+    // Safety: thread_sync_blocking keeps this alive until BYOND returns, which is after this has run
+    let data = unsafe { &mut *(data as *mut BlockingData<F>) };
+    let callback = data.callback.take().unwrap();
+    match run_caught(|| callback().try_into().map_err(Error::from)).and_then(|result| result) {
+        Ok(value) => value.into_inner(),
+        Err(e) => {
+            data.result = Err(e);
+            ByondValue::null().into_inner()
+        }
+    }
 }
 
-pub fn thread_sync<F>(callback: F, block: bool) -> ByondValue
+/// Runs `callback` on the main thread, blocking until it's done, and returns its result as a [`ByondValue`].
+///
+/// Don't hold anything the main thread might be waiting for while calling this, or neither thread will ever continue.
+/// ```ignore
+/// std::thread::spawn(|| {
+///     let answer = thread_sync_blocking(|| 42.0)?;
+/// });
+/// ```
+pub fn thread_sync_blocking<F, R>(callback: F) -> Result<ByondValue, Error>
 where
-    F: FnOnce() -> ByondValue + Send + 'static,
+    F: FnOnce() -> R + Send + 'static,
+    R: TryInto<ByondValue>,
+    Error: From<R::Error>,
 {
-    let data = Box::new(CallbackData {
+    let mut data = BlockingData {
         callback: Some(callback),
+        result: Ok(()),
+    };
+    let data_ptr = &mut data as *mut BlockingData<F> as *mut c_void;
+
+    let value = ByondValue(unsafe {
+        byond().Byond_ThreadSync(Some(blocking_trampoline::<F, R>), data_ptr, true)
     });
+    data.result.map(|_| value)
+}
+
+/// Like [`thread_sync_blocking`], but hands the callback a [`MainThread`] token.
+pub fn thread_sync_with_token<F, R>(callback: F) -> Result<ByondValue, Error>
+where
+    F: FnOnce(&MainThread) -> R + Send + 'static,
+    R: TryInto<ByondValue>,
+    Error: From<R::Error>,
+{
+    // Safety: thread_sync_blocking only runs callbacks on the main thread
+    thread_sync_blocking(move || callback(&unsafe { MainThread::new_unchecked() }))
+}
+
+/// Runs `callback` on the main thread and returns what it returns, waiting for it if `block` is true.
+/// Returns null straight away if not blocking, or if the callback panics.
+#[deprecated(note = "use thread_sync_blocking, or thread_sync_deferred to not wait")]
+pub fn thread_sync<F>(callback: F, block: bool) -> ByondValue
+where
+    F: FnOnce() -> ByondValue + Send + 'static,
+{
+    if block {
+        thread_sync_blocking(callback).unwrap_or_default()
+    } else {
+        thread_sync_deferred(callback);
+        ByondValue::null()
+    }
+}
+
+struct DeferredData<F, R> {
+    callback: F,
+    sender: SyncSender<Result<R, Error>>,
+}
+
+extern "C" fn deferred_trampoline<F, R>(data: *mut c_void) -> CByondValue
+where
+    F: FnOnce() -> R + Send,
+    R: Send,
+{
+    // Safety: this is the Box leaked by thread_sync_deferred, and BYOND only runs each callback once
+    let data = unsafe { Box::from_raw(data as *mut DeferredData<F, R>) };
+    // Nobody might be listening anymore, which is fine
+    let _ = data.sender.send(run_caught(data.callback));
+    ByondValue::null().into_inner()
+}
+
+/// Queues `callback` to run on the main thread without waiting for it, returning a handle to get its result from.
+///
+/// The result can be anything that can be sent between threads, it doesn't have to be a [`ByondValue`].
+/// Values that only exist for as long as the callback runs, like new strings, shouldn't be sent back.
+pub fn thread_sync_deferred<F, R>(callback: F) -> ThreadSyncHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let data = Box::new(DeferredData { callback, sender });
     let data_ptr = Box::into_raw(data) as *mut c_void;
 
-    ByondValue(unsafe { byond().Byond_ThreadSync(Some(trampoline::<F>), data_ptr, block) })
+    unsafe { byond().Byond_ThreadSync(Some(deferred_trampoline::<F, R>), data_ptr, false) };
+    ThreadSyncHandle { receiver }
+}
+
+/// Like [`thread_sync_deferred`], but hands the callback a [`MainThread`] token.
+pub fn thread_sync_deferred_with_token<F, R>(callback: F) -> ThreadSyncHandle<R>
+where
+    F: FnOnce(&MainThread) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    // Safety: thread_sync_deferred only runs callbacks on the main thread
+    thread_sync_deferred(move || callback(&unsafe { MainThread::new_unchecked() }))
+}
+
+/// The result of a callback queued by [`thread_sync_deferred`], once BYOND gets around to running it.
+///
+/// Never wait on this from the main thread, the callback can't run until the main thread is free.
+pub struct ThreadSyncHandle<R> {
+    receiver: Receiver<Result<R, Error>>,
+}
+
+impl<R> ThreadSyncHandle<R> {
+    /// A handle for a result that's already known, like from a callback that ran straight away
+    pub(crate) fn ready(result: Result<R, Error>) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        // Can't fail, the receiver is right here and there's room for one
+        let _ = sender.send(result);
+        Self { receiver }
+    }
+
+    /// Blocks until the callback has run, and returns its result.
+    pub fn wait(self) -> Result<R, Error> {
+        self.receiver
+            .recv()
+            .unwrap_or(Err(Error::UnknownByondError))
+    }
+
+    /// Waits up to `timeout` for the callback to run, returning [`None`] if it hasn't yet.
+    ///
+    /// Like [`ThreadSyncHandle::try_wait`], the result is only returned once.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Result<R, Error>> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Gets the result if the callback has run, without blocking.
+    ///
+    /// The result is only returned once, later calls return [`None`].
+    pub fn try_wait(&self) -> Option<Result<R, Error>> {
+        self.receiver.try_recv().ok()
+    }
 }
@@ -0,0 +1,238 @@
+//! Spreading long passes over several ticks, so they don't cause lag spikes.
+//!
+//! A [`TickBudget`] works out how much of the current tick is left from `world.tick_usage` and `world.tick_lag`,
+//! so loops can check [`TickBudget::should_yield`] and stop before they overrun it.
+//!
+//! [`run_chunked`] does the splitting up for you: it runs a closure on every item of an iterator, as many as fit in
+//! each tick, resuming every tick through the generated `byondapi_chunked_loop` proc.
+//! ```ignore
+//! let run = run_chunked(turfs, 80.0, |turf| update_lighting(turf))?;
+//! // Later on
+//! if run.is_finished() {
+//!     println!("Took {} ticks", run.per_tick().len());
+//! }
+//! ```
+//!
+//! Requires the generated `bindings.dm` to be included in your DM project.
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    byond_string, error::report_error, global_call::call_global_id, prelude::ByondValue,
+    world::World, Error,
+};
+
+inventory::submit! {
+    crate::binds::DmCode(r#"
+/proc/byondapi_chunked_loop(id)
+	set waitfor = FALSE
+	while(byondapi_chunked_step(id))
+		sleep(world.tick_lag)
+"#)
+}
+
+/// How much of the current tick is left to use
+#[derive(Debug, Clone, Copy)]
+pub struct TickBudget {
+    deadline: Instant,
+}
+
+impl TickBudget {
+    /// Makes a budget that runs out once `world.tick_usage` reaches `limit`, as a percentage of the tick. Must be
+    /// called on the main thread.
+    pub fn new(limit: f32) -> Result<Self, Error> {
+        let world = World::new();
+        let left = (limit - world.tick_usage()?) / 100.0 * world.tick_lag()?;
+        // tick_lag is in deciseconds
+        let left = Duration::from_secs_f32(left.max(0.0) / 10.0);
+        Ok(Self {
+            deadline: Instant::now() + left,
+        })
+    }
+
+    /// Whether the budget has run out, and whatever's using it should wait for the next tick
+    pub fn should_yield(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// How long until the budget runs out
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+#[derive(Default)]
+struct Progress {
+    per_tick: Vec<usize>,
+    finished: bool,
+    failed: bool,
+    cancelled: bool,
+}
+
+/// Lets runs over different iterators sit in the same registry
+trait Step {
+    /// Runs as many items as fit in the budget, returning whether there are any left
+    fn step(&mut self) -> bool;
+}
+
+struct Run<I, F> {
+    items: I,
+    f: F,
+    limit: f32,
+    progress: Rc<RefCell<Progress>>,
+}
+
+impl<I: Iterator, F: FnMut(I::Item) -> Result<(), Error>> Run<I, F> {
+    fn run_tick(&mut self) -> Result<bool, Error> {
+        let budget = TickBudget::new(self.limit)?;
+        let mut processed = 0;
+        let result = loop {
+            let Some(item) = self.items.next() else {
+                break Ok(false);
+            };
+            if let Err(e) = (self.f)(item) {
+                break Err(e);
+            }
+            processed += 1;
+            // At least one item a tick, so it always gets somewhere
+            if budget.should_yield() {
+                break Ok(true);
+            }
+        };
+        self.progress.borrow_mut().per_tick.push(processed);
+        result
+    }
+}
+
+impl<I: Iterator, F: FnMut(I::Item) -> Result<(), Error>> Step for Run<I, F> {
+    fn step(&mut self) -> bool {
+        if self.progress.borrow().cancelled {
+            self.progress.borrow_mut().finished = true;
+            return false;
+        }
+        let more = self.run_tick().unwrap_or_else(|e| {
+            self.progress.borrow_mut().failed = true;
+            report_error(e);
+            false
+        });
+        if !more {
+            self.progress.borrow_mut().finished = true;
+        }
+        more
+    }
+}
+
+/// Holds a run's place in the registry while it's being stepped
+struct Stepping;
+
+impl Step for Stepping {
+    fn step(&mut self) -> bool {
+        true
+    }
+}
+
+thread_local! {
+    /// Runs that are still going. Only ever touched on the main thread.
+    static RUNS: RefCell<BTreeMap<u32, Box<dyn Step>>> = const { RefCell::new(BTreeMap::new()) };
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Starts running `f` on each of `items`, as many as fit before `world.tick_usage` reaches `limit` each tick. Must
+/// be called on the main thread.
+///
+/// The first tick's worth runs straight away. If `f` fails the run stops, and the error is reported through
+/// `stack_trace`.
+pub fn run_chunked<I, F>(items: I, limit: f32, f: F) -> Result<ChunkedRun, Error>
+where
+    I: IntoIterator,
+    I::IntoIter: 'static,
+    F: FnMut(I::Item) -> Result<(), Error> + 'static,
+{
+    let progress = Rc::new(RefCell::new(Progress::default()));
+    let run = Run {
+        items: items.into_iter(),
+        f,
+        limit,
+        progress: progress.clone(),
+    };
+    let id = RUNS.with(|runs| {
+        let mut runs = runs.borrow_mut();
+        let id = NEXT_ID.with(|next| loop {
+            // Ids are sent to DM as numbers, so they have to stay within the range a float can represent exactly
+            next.set((next.get() + 1) % (1 << 24));
+            // Skip ids still in use, in case the ids have wrapped around
+            if !runs.contains_key(&next.get()) {
+                break next.get();
+            }
+        });
+        runs.insert(id, Box::new(run));
+        id
+    });
+    call_global_id(
+        byond_string!("byondapi_chunked_loop"),
+        &[ByondValue::new_num(id as f32)],
+    )?;
+    Ok(ChunkedRun { progress })
+}
+
+/// Keeps track of a run started by [`run_chunked`]. Can only be used on the main thread.
+///
+/// Dropping this doesn't stop the run.
+#[derive(Clone)]
+pub struct ChunkedRun {
+    progress: Rc<RefCell<Progress>>,
+}
+
+impl ChunkedRun {
+    /// How many items were processed in each tick the run has had so far
+    pub fn per_tick(&self) -> Vec<usize> {
+        self.progress.borrow().per_tick.clone()
+    }
+
+    /// How many items have been processed so far
+    pub fn processed(&self) -> usize {
+        self.progress.borrow().per_tick.iter().sum()
+    }
+
+    /// Whether every item has been processed, or the run has stopped early
+    pub fn is_finished(&self) -> bool {
+        self.progress.borrow().finished
+    }
+
+    /// Whether the run was stopped by an error
+    pub fn failed(&self) -> bool {
+        self.progress.borrow().failed
+    }
+
+    /// Stops the run before its next tick
+    pub fn cancel(&self) {
+        self.progress.borrow_mut().cancelled = true;
+    }
+}
+
+#[crate::bind]
+fn byondapi_chunked_step(id: ByondValue) -> Result<ByondValue, Error> {
+    let id = id.get_number()? as u32;
+    // Swapped out while it runs, so items can start runs of their own without being given its id
+    let Some(mut run) = RUNS.with(|runs| {
+        runs.borrow_mut()
+            .get_mut(&id)
+            .map(|run| std::mem::replace(run, Box::new(Stepping)))
+    }) else {
+        return Ok(false.into());
+    };
+    let more = run.step();
+    RUNS.with(|runs| {
+        let mut runs = runs.borrow_mut();
+        if more {
+            runs.insert(id, run);
+        } else {
+            runs.remove(&id);
+        }
+    });
+    Ok(more.into())
+}
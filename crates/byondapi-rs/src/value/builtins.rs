@@ -1,12 +1,13 @@
 use super::ByondValue;
-use crate::{static_global::byond, Error};
+use crate::{error::ErrorContext, static_global::byond, Error};
 
 impl ByondValue {
     /// Try to get a length of a string in bytes, lists in number of assoc elements probably, will fail if it's neither a list or string
     pub fn builtin_length(&self) -> Result<ByondValue, Error> {
         let mut result = ByondValue::new();
         unsafe {
-            map_byond_error!(byond().Byond_Length(&self.0, &mut result.0))?;
+            map_byond_error!(byond().Byond_Length(&self.0, &mut result.0))
+                .map_err(|e| e.with_context(ErrorContext::new("length").target(*self)))?;
         }
         Ok(result)
     }
@@ -22,7 +23,8 @@ impl ByondValue {
                 arglist.as_ptr().cast(),
                 arglist.len() as u32,
                 &mut result.0
-            ))?;
+            ))
+            .map_err(|e| e.with_context(ErrorContext::new("new").name_value(&value_type)))?;
         }
         Ok(result)
     }
@@ -36,7 +38,8 @@ impl ByondValue {
         };
         let mut result = ByondValue::new();
         unsafe {
-            map_byond_error!(byond().Byond_NewArglist(&value_type.0, &arglist.0, &mut result.0))?;
+            map_byond_error!(byond().Byond_NewArglist(&value_type.0, &arglist.0, &mut result.0))
+                .map_err(|e| e.with_context(ErrorContext::new("new").name_value(&value_type)))?;
         }
         Ok(result)
     }
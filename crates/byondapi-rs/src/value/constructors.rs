@@ -47,6 +47,15 @@ impl ByondValue {
         })
     }
 
+    /// Gets DM's `world`
+    pub fn world() -> Self {
+        Self::new_ref(ValueType::World, 0)
+    }
+
+    /// Despite the name this is not a way to reach `global.` variables, see [`crate::global_var`] for that.
+    #[deprecated(
+        note = "use ByondValue::world() for the world, or byondapi::global_var for global variables"
+    )]
     pub fn new_global_ref() -> Self {
         Self(CByondValue {
             type_: ValueType::World as u8,
@@ -1,9 +1,9 @@
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 
 use byondapi_sys::{u4c, ByondValueType, CByondValue};
 
 use super::ByondValue;
-use crate::{static_global::byond, Error};
+use crate::{error::ErrorContext, static_global::byond, Error};
 
 /// # Compatibility with the C++ API
 impl ByondValue {
@@ -26,7 +26,7 @@ impl ByondValue {
         if self.is_num() {
             Ok(unsafe { byond().ByondValue_GetNum(&self.0) })
         } else {
-            Err(Error::NotANum(*self))
+            Err(Error::NotANum(*self).with_context(ErrorContext::new("get_number").target(*self)))
         }
     }
 
@@ -125,16 +125,19 @@ impl ByondValue {
 impl ByondValue {
     /// Read a variable through the ref. Fails if this isn't a ref type.
     pub fn read_var<T: Into<Vec<u8>>>(&self, name: T) -> Result<ByondValue, Error> {
-        if self.is_num() || self.is_str() || self.is_ptr() || self.is_null() || self.is_list() {
-            return Err(Error::NotReferencable(*self));
-        }
         let c_string = CString::new(name).unwrap();
         let c_str = c_string.as_c_str();
+        let context = |e: Error| e.with_context(self.var_context("read_var", c_str));
+
+        if self.is_num() || self.is_str() || self.is_ptr() || self.is_null() || self.is_list() {
+            return Err(context(Error::NotReferencable(*self)));
+        }
 
         let mut new_value = ByondValue::new();
 
         unsafe {
-            map_byond_error!(byond().Byond_ReadVar(&self.0, c_str.as_ptr(), &mut new_value.0))?;
+            map_byond_error!(byond().Byond_ReadVar(&self.0, c_str.as_ptr(), &mut new_value.0))
+                .map_err(context)?;
         }
 
         Ok(new_value)
@@ -150,6 +153,7 @@ impl ByondValue {
         let c_str = c_string.as_c_str();
 
         unsafe { map_byond_error!(byond().Byond_WriteVar(&self.0, c_str.as_ptr(), &other.0)) }
+            .map_err(|e| e.with_context(self.var_context("write_var", c_str)))
     }
 
     /// Call a proc using self as src. Fails if this isn't a ref type.
@@ -166,10 +170,11 @@ impl ByondValue {
     ) -> Result<ByondValue, Error> {
         let c_string = CString::new(name).unwrap();
         let c_str = c_string.as_c_str();
+        let context = |e: Error| e.with_context(self.var_context("call", c_str));
 
         let str_id = unsafe { byond().Byond_GetStrId(c_str.as_ptr()) };
         if str_id == crate::sys::u2c::MAX as u32 {
-            return Err(Error::InvalidProc(c_string));
+            return Err(context(Error::InvalidProc(c_string.clone())));
         }
 
         let ptr = args.as_ptr();
@@ -181,23 +186,38 @@ impl ByondValue {
                 ptr as *const byondapi_sys::CByondValue,
                 args.len() as u32,
                 &mut new_value.0
-            ))?;
+            ))
+            .map_err(context)?;
         }
 
         Ok(new_value)
     }
+
+    /// Describes an operation on this value involving `name`, for errors
+    fn var_context(&self, operation: &'static str, name: &CStr) -> ErrorContext {
+        ErrorContext::new(operation)
+            .name(name.to_string_lossy())
+            .target(*self)
+    }
+
+    /// Like [`ByondValue::var_context`], for names given as string ids
+    fn id_context(&self, operation: &'static str, name: u4c) -> ErrorContext {
+        ErrorContext::new(operation).name_id(name).target(*self)
+    }
 }
 
 /// # Accessors by ids
 impl ByondValue {
     /// Read a variable through the ref. Fails if this isn't a ref type, or the id is invalid.
     pub fn read_var_id(&self, name: u4c) -> Result<ByondValue, Error> {
+        let context = |e: Error| e.with_context(self.id_context("read_var", name));
         if self.is_num() || self.is_str() || self.is_ptr() || self.is_null() || self.is_list() {
-            return Err(Error::NotReferencable(*self));
+            return Err(context(Error::NotReferencable(*self)));
         }
         let mut new_value = ByondValue::new();
         unsafe {
-            map_byond_error!(byond().Byond_ReadVarByStrId(&self.0, name, &mut new_value.0))?;
+            map_byond_error!(byond().Byond_ReadVarByStrId(&self.0, name, &mut new_value.0))
+                .map_err(context)?;
         }
 
         Ok(new_value)
@@ -206,6 +226,7 @@ impl ByondValue {
     /// Write to a variable through the ref. Fails if this isn't a ref type, or the id is invalid.
     pub fn write_var_id(&mut self, name: u4c, other: &ByondValue) -> Result<(), Error> {
         unsafe { map_byond_error!(byond().Byond_WriteVarByStrId(&self.0, name, &other.0)) }
+            .map_err(|e| e.with_context(self.id_context("write_var", name)))
     }
 
     /// Call a proc using self as src. Fails if this isn't a ref type, or the id is invalid.
@@ -225,7 +246,8 @@ impl ByondValue {
                 ptr as *const byondapi_sys::CByondValue,
                 args.len() as u32,
                 &mut new_value.0
-            ))?;
+            ))
+            .map_err(|e| e.with_context(self.id_context("call", name)))?;
         }
 
         Ok(new_value)
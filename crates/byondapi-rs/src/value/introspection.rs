@@ -0,0 +1,70 @@
+//! Discovering what procs and vars a value has before touching them.
+//!
+//! BYONDAPI has no primitives for most of this, so these go through helper procs in the generated `bindings.dm`.
+use super::ByondValue;
+use crate::{byond_string, error::ErrorContext, global_call::call_global_id, Error};
+
+inventory::submit! {
+    crate::binds::DmCode(r#"
+/proc/byondapi_hascall(datum/target, proc_name)
+	return hascall(target, proc_name)
+
+/proc/byondapi_hasvar(datum/target, var_name)
+	return (var_name in target.vars)
+
+/proc/byondapi_initial(datum/target, var_name)
+	return initial(target.vars[var_name])
+
+/proc/byondapi_istype(thing, path)
+	return istype(thing, text2path(path))
+"#)
+}
+
+/// # Introspection
+impl ByondValue {
+    fn check_introspectable(&self, operation: &'static str) -> Result<(), Error> {
+        if self.is_num() || self.is_str() || self.is_ptr() || self.is_null() || self.is_list() {
+            return Err(Error::NotReferencable(*self)
+                .with_context(ErrorContext::new(operation).target(*self)));
+        }
+        Ok(())
+    }
+
+    /// Checks if this value has a proc with this name, equivalent to DM's `hascall`. Fails if this isn't a ref type.
+    pub fn has_proc<T: Into<Vec<u8>>>(&self, name: T) -> Result<bool, Error> {
+        self.check_introspectable("has_proc")?;
+        let name = ByondValue::new_str(name)?;
+        call_global_id(byond_string!("byondapi_hascall"), &[*self, name])?.get_bool()
+    }
+
+    /// Checks if this value has a var with this name. Fails if this isn't a ref type.
+    pub fn has_var<T: Into<Vec<u8>>>(&self, name: T) -> Result<bool, Error> {
+        self.check_introspectable("has_var")?;
+        let name = ByondValue::new_str(name)?;
+        call_global_id(byond_string!("byondapi_hasvar"), &[*self, name])?.get_bool()
+    }
+
+    /// Gets the names of every var this value has, read from its `vars` list. Fails if this isn't a ref type.
+    pub fn var_names(&self) -> Result<Vec<String>, Error> {
+        self.check_introspectable("var_names")?;
+        self.read_var_id(byond_string!("vars"))?
+            .get_list_values()?
+            .iter()
+            .map(ByondValue::get_string)
+            .collect()
+    }
+
+    /// Gets the compile-time value of a var, equivalent to DM's `initial(src.vars[name])`.
+    /// Fails if this isn't a ref type.
+    pub fn initial_var<T: Into<Vec<u8>>>(&self, name: T) -> Result<ByondValue, Error> {
+        self.check_introspectable("initial_var")?;
+        let name = ByondValue::new_str(name)?;
+        call_global_id(byond_string!("byondapi_initial"), &[*self, name])
+    }
+
+    /// Checks if this value is of the type path or one of its subtypes, equivalent to DM's `istype`.
+    pub fn is_type<T: Into<Vec<u8>>>(&self, path: T) -> Result<bool, Error> {
+        let path = ByondValue::new_str(path)?;
+        call_global_id(byond_string!("byondapi_istype"), &[*self, path])?.get_bool()
+    }
+}
@@ -1,11 +1,12 @@
-use crate::{byond_string, static_global::byond, value::ByondValue, Error};
+use crate::{byond_string, error::ErrorContext, static_global::byond, value::ByondValue, Error};
 /// List stuff goes here, Keep in mind that all indexing method starts at zero instead of one like byondland
 impl ByondValue {
     /// Gets an array of all the list values, this means values for assoc lists and just items in the listfor regular lists
     pub fn get_list_values(&self) -> Result<Vec<ByondValue>, Error> {
         use std::cell::RefCell;
+        let context = |e: Error| e.with_context(ErrorContext::new("get_list_values").target(*self));
         if !self.is_list() {
-            return Err(Error::NotAList(*self));
+            return Err(context(Error::NotAList(*self)));
         }
 
         thread_local! {
@@ -27,7 +28,8 @@ impl ByondValue {
                             &self.0,
                             buff.as_mut_ptr().cast(),
                             &mut len
-                        ))?
+                        ))
+                        .map_err(context)?
                     };
                     // Safety: buffer should be written to at this point
                     unsafe { buff.set_len(len as usize) };
@@ -38,7 +40,7 @@ impl ByondValue {
                     unsafe { buff.set_len(len as usize) };
                     Ok(std::mem::take(buff))
                 }
-                (false, 0) => Err(Error::get_last_byond_error()),
+                (false, 0) => Err(context(Error::get_last_byond_error())),
             }
         })
     }
@@ -47,8 +49,9 @@ impl ByondValue {
     /// Reads items as key,value pairs from an associative list, storing them sequentially as key1, value1, key2, value2, etc.
     pub fn get_list(&self) -> Result<Vec<ByondValue>, Error> {
         use std::cell::RefCell;
+        let context = |e: Error| e.with_context(ErrorContext::new("get_list").target(*self));
         if !self.is_list() {
-            return Err(Error::NotAList(*self));
+            return Err(context(Error::NotAList(*self)));
         }
 
         thread_local! {
@@ -70,7 +73,8 @@ impl ByondValue {
                             &self.0,
                             buff.as_mut_ptr().cast(),
                             &mut len
-                        ))?
+                        ))
+                        .map_err(context)?
                     };
                     // Safety: buffer should be written to at this point
                     unsafe { buff.set_len(len as usize) };
@@ -81,7 +85,7 @@ impl ByondValue {
                     unsafe { buff.set_len(len as usize) };
                     Ok(std::mem::take(buff))
                 }
-                (false, 0) => Err(Error::get_last_byond_error()),
+                (false, 0) => Err(context(Error::get_last_byond_error())),
             }
         })
     }
@@ -95,14 +99,18 @@ impl ByondValue {
                 list.len() as u32
             ))
         }
+        .map_err(|e| e.with_context(ErrorContext::new("write_list").target(*self)))
     }
 
     /// Reads a value by key through the ref. Fails if this isn't a list.
     pub fn read_list_index<I: TryInto<ByondValue>>(&self, index: I) -> Result<ByondValue, Error> {
+        let context = |e: Error| e.with_context(ErrorContext::new("read_list_index").target(*self));
         if !self.is_list() {
-            return Err(Error::NotAList(*self));
+            return Err(context(Error::NotAList(*self)));
         }
-        let index: ByondValue = index.try_into().map_err(|_| Error::InvalidConversion)?;
+        let index: ByondValue = index
+            .try_into()
+            .map_err(|_| context(Error::conversion_into::<I>()))?;
         self.read_list_index_internal(&index)
     }
 
@@ -112,11 +120,17 @@ impl ByondValue {
         index: I,
         value: V,
     ) -> Result<(), Error> {
+        let context =
+            |e: Error| e.with_context(ErrorContext::new("write_list_index").target(*self));
         if !self.is_list() {
-            return Err(Error::NotAList(*self));
+            return Err(context(Error::NotAList(*self)));
         }
-        let index: ByondValue = index.try_into().map_err(|_| Error::InvalidConversion)?;
-        let value: ByondValue = value.try_into().map_err(|_| Error::InvalidConversion)?;
+        let index: ByondValue = index
+            .try_into()
+            .map_err(|_| context(Error::conversion_into::<I>()))?;
+        let value: ByondValue = value
+            .try_into()
+            .map_err(|_| context(Error::conversion_into::<V>()))?;
         self.write_list_index_internal(&index, &value)
     }
 
@@ -124,7 +138,8 @@ impl ByondValue {
     pub fn read_list_index_internal(&self, index: &ByondValue) -> Result<ByondValue, Error> {
         let mut result = ByondValue::new();
         unsafe {
-            map_byond_error!(byond().Byond_ReadListIndex(&self.0, &index.0, &mut result.0))?;
+            map_byond_error!(byond().Byond_ReadListIndex(&self.0, &index.0, &mut result.0))
+                .map_err(|e| e.with_context(self.index_context("read_list_index", index)))?;
         }
         Ok(result)
     }
@@ -136,24 +151,33 @@ impl ByondValue {
         value: &ByondValue,
     ) -> Result<(), Error> {
         unsafe {
-            map_byond_error!(byond().Byond_WriteListIndex(&self.0, &index.0, &value.0))?;
+            map_byond_error!(byond().Byond_WriteListIndex(&self.0, &index.0, &value.0))
+                .map_err(|e| e.with_context(self.index_context("write_list_index", index)))?;
         }
         Ok(())
     }
 
+    /// Describes an operation on this list at `index`, for errors
+    fn index_context(&self, operation: &'static str, index: &ByondValue) -> ErrorContext {
+        ErrorContext::new(operation).name_value(index).target(*self)
+    }
+
     /// Pushes a value into a list
     pub fn push_list(&mut self, value: ByondValue) -> Result<(), Error> {
+        let context = |e: Error| e.with_context(ErrorContext::new("push_list").target(*self));
         if !self.is_list() {
-            return Err(Error::NotAList(*self));
+            return Err(context(Error::NotAList(*self)));
         }
-        self.call_id(byond_string!("Add"), &[value])?;
+        self.call_id(byond_string!("Add"), &[value])
+            .map_err(context)?;
         Ok(())
     }
 
     /// Pops a value from a list
     pub fn pop_list(&mut self) -> Result<Option<ByondValue>, Error> {
+        let context = |e: Error| e.with_context(ErrorContext::new("pop_list").target(*self));
         if !self.is_list() {
-            return Err(Error::NotAList(*self));
+            return Err(context(Error::NotAList(*self)));
         }
         let len = self.builtin_length()?.get_number()? as usize;
         if len == 0 {
@@ -3,6 +3,7 @@
 use byondapi_sys::{ByondValueType, CByondValue};
 
 use crate::static_global::byond;
+use types::ValueType;
 
 /// [Newtype](https://doc.rust-lang.org/rust-by-example/generics/new_types.html) pattern over [`CByondValue`]
 #[repr(transparent)]
@@ -16,15 +17,20 @@ pub mod builtins;
 pub mod constructors;
 pub mod conversion;
 pub mod functions;
+pub mod introspection;
 pub mod list;
 pub mod pointer;
+pub mod ref_id;
 pub mod trait_impls;
 pub mod types;
 
-/// TODO: Use a Byond_IsPtr here instead of checking the type by hand
+/// TODO: Use a Byond_IsPtr here instead of checking the type by hand. Only proc var pointers have a type we know,
+/// [`ByondPointer::new`](pointer::ByondPointer::new) recognises the rest.
 fn is_pointer_shim(value: &ByondValue) -> bool {
-    let type_ = value.get_type();
-    type_ == 0x3C
+    matches!(
+        ValueType::try_from(value.get_type()),
+        Ok(ValueType::Pointer)
+    )
 }
 
 // Typechecking
@@ -1,5 +1,7 @@
-use super::ByondValue;
-use crate::{static_global::byond, Error};
+use std::marker::PhantomData;
+
+use super::{types::ValueType, ByondValue};
+use crate::{error::ErrorContext, static_global::byond, Error};
 
 #[repr(transparent)]
 pub struct ByondValuePointer(pub ByondValue);
@@ -15,7 +17,8 @@ impl ByondValuePointer {
         let mut new_value = ByondValue::new();
 
         unsafe {
-            map_byond_error!(byond().Byond_ReadPointer(&self.0 .0, &mut new_value.0))?;
+            map_byond_error!(byond().Byond_ReadPointer(&self.0 .0, &mut new_value.0))
+                .map_err(|e| e.with_context(ErrorContext::new("read_pointer").target(self.0)))?;
         }
 
         Ok(new_value)
@@ -24,6 +27,7 @@ impl ByondValuePointer {
     /// Write a [`ByondValue`] through this pointer
     pub fn write(&self, value: &ByondValue) -> Result<(), Error> {
         unsafe { map_byond_error!(byond().Byond_WritePointer(&self.0 .0, &value.0)) }
+            .map_err(|e| e.with_context(ErrorContext::new("write_pointer").target(self.0)))
     }
 }
 
@@ -38,3 +42,101 @@ impl TryFrom<ByondValue> for ByondValuePointer {
         }
     }
 }
+
+/// A pointer which converts what it reads and writes through [`TryFrom`]/[`TryInto`] [`ByondValue`].
+///
+/// Recognises every kind of pointer BYOND makes, including pointers to proc vars, object vars, list elements and
+/// globals.
+#[repr(transparent)]
+pub struct ByondPointer<T> {
+    pointer: ByondValue,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ByondPointer<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ByondPointer<T> {}
+
+/// BYOND has a pointer type for each kind of thing that can be pointed at (proc vars, object vars, list elements,
+/// globals...) and BYONDAPI has no way to ask if something is a pointer. So anything that isn't a type we know about
+/// is checked by trying to read through it.
+fn is_unlisted_pointer(value: &ByondValue) -> bool {
+    if ValueType::try_from(value.get_type()).is_ok() {
+        return false;
+    }
+    let mut result = ByondValue::new();
+    // Safety: result is initialized, which is all the header asks for. The read is checked like every other BYONDAPI
+    // call, so it fails for values that can't be read through rather than reading them.
+    unsafe { byond().Byond_ReadPointer(&value.0, &mut result.0) }
+}
+
+impl<T> ByondPointer<T> {
+    /// If the value is actually a pointer, this will wrap it in a comfy type. Otherwise it fails.
+    ///
+    /// Values of types this crate doesn't know are checked by trying to read through them, so this can leave an
+    /// error behind in `Byond_LastError` even when it succeeds. It's only worth calling on values meant as pointers.
+    pub fn new(value: ByondValue) -> Result<Self, Error> {
+        if value.is_ptr() || is_unlisted_pointer(&value) {
+            Ok(Self {
+                pointer: value,
+                _marker: PhantomData,
+            })
+        } else {
+            Err(Error::NotAPtr(value))
+        }
+    }
+
+    /// Gets the pointer itself
+    pub fn as_value(&self) -> &ByondValue {
+        &self.pointer
+    }
+
+    /// Read from this pointer without converting
+    pub fn read_raw(&self) -> Result<ByondValue, Error> {
+        ByondValuePointer(self.pointer).read()
+    }
+
+    /// Write a [`ByondValue`] through this pointer without converting
+    pub fn write_raw(&self, value: &ByondValue) -> Result<(), Error> {
+        ByondValuePointer(self.pointer).write(value)
+    }
+}
+
+impl<T: TryFrom<ByondValue>> ByondPointer<T> {
+    /// Read from this pointer and convert it to `T`
+    pub fn read(&self) -> Result<T, Error> {
+        let value = self.read_raw()?;
+        T::try_from(value).map_err(|_| Error::conversion_from::<T>(&value))
+    }
+}
+
+impl<T: TryInto<ByondValue>> ByondPointer<T> {
+    /// Convert `value` and write it through this pointer
+    pub fn write(&self, value: T) -> Result<(), Error> {
+        let value = value
+            .try_into()
+            .map_err(|_| Error::conversion_into::<T>())?;
+        self.write_raw(&value)
+    }
+}
+
+impl<T> TryFrom<ByondValue> for ByondPointer<T> {
+    type Error = Error;
+
+    fn try_from(value: ByondValue) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl<T> From<ByondValuePointer> for ByondPointer<T> {
+    fn from(value: ByondValuePointer) -> Self {
+        Self {
+            pointer: value.0,
+            _marker: PhantomData,
+        }
+    }
+}
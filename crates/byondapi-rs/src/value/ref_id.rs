@@ -0,0 +1,94 @@
+//! Converting values to and from BYOND's text refs, the `[0x2000001]` strings made by `ref()` and `"\ref[thing]"`.
+use std::{fmt, str::FromStr};
+
+use super::{types::ValueType, ByondValue};
+use crate::{map::byond_locateby, Error};
+
+/// A BYOND ref id, the type byte in the top 8 bits and the ref number in the bottom 24.
+///
+/// Formats as and parses from BYOND's `[0x2000001]` form without calling into BYOND,
+/// parsing also accepts it without the brackets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RefId(u32);
+
+impl RefId {
+    pub fn new(typ: u8, id: u32) -> Self {
+        Self((typ as u32) << 24 | (id & 0xFFFFFF))
+    }
+
+    pub fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    /// The whole id, as it appears in the text ref
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+
+    pub fn type_byte(&self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    /// The type of value this refers to, if it's one we know about
+    pub fn value_type(&self) -> Option<ValueType> {
+        ValueType::try_from(self.type_byte()).ok()
+    }
+
+    /// The ref number, same as [`ByondValue::get_ref`]
+    pub fn id(&self) -> u32 {
+        self.0 & 0xFFFFFF
+    }
+
+    /// Finds the value this refers to, null if it doesn't exist anymore
+    pub fn locate(&self) -> Result<ByondValue, Error> {
+        byond_locateby(&ByondValue::new_str(self.to_string())?)
+    }
+}
+
+impl fmt::Display for RefId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[0x{:x}]", self.0)
+    }
+}
+
+impl FromStr for RefId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidConversion {
+            expected: "ref",
+            actual: format!("{s:?}"),
+        };
+        let s = s.trim();
+        let s = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(s);
+        let hex = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .ok_or_else(invalid)?;
+        u32::from_str_radix(hex, 16)
+            .map(Self)
+            .map_err(|_| invalid())
+    }
+}
+
+/// # Text refs
+impl ByondValue {
+    /// Gets this value's [`RefId`]. Fails if this isn't a ref type.
+    pub fn ref_id(&self) -> Result<RefId, Error> {
+        Ok(RefId::new(self.get_type(), self.get_ref()?))
+    }
+
+    /// Gets this value's text ref, the same as DM's `ref(src)`. Fails if this isn't a ref type.
+    pub fn to_ref_string(&self) -> Result<String, Error> {
+        Ok(self.ref_id()?.to_string())
+    }
+
+    /// Finds the value a text ref refers to, like DM's `locate(ref)`.
+    /// Fails if the text isn't a ref, returns null if the thing it refers to doesn't exist anymore.
+    pub fn from_ref_string(text: &str) -> Result<ByondValue, Error> {
+        text.parse::<RefId>()?.locate()
+    }
+}
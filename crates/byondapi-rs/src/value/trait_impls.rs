@@ -13,16 +13,15 @@ impl PartialEq for ByondValue {
 // Debug!
 impl Debug for ByondValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let type_enum: ValueType = self
-            .0
-            .type_
-            .try_into()
-            .unwrap_or_else(|_| panic!("Unimplemented type: {:X}", self.0.type_));
-        let typ = format!("{type_enum:?}");
+        let type_enum = ValueType::try_from(self.0.type_).ok();
+        let typ = match type_enum {
+            Some(type_enum) => format!("{type_enum:?}"),
+            None => format!("Unknown({:X})", self.0.type_),
+        };
 
         let value = match type_enum {
-            ValueType::Null => "NULL".to_owned(),
-            ValueType::Number => format!("{}", unsafe { self.0.data.ref_ as f32 }),
+            Some(ValueType::Null) => "NULL".to_owned(),
+            Some(ValueType::Number) => format!("{}", unsafe { self.0.data.ref_ as f32 }),
             _ => format!("[{:X}]", unsafe { self.0.data.ref_ }),
         };
 
@@ -59,4 +59,7 @@ pub enum ValueType {
 
     Number = 0x2A,
     Appearance = 0x3A,
+
+    /// Pointer to a proc var, other kinds of pointer have their own types which aren't listed here
+    Pointer = 0x3C,
 }
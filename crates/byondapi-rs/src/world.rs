@@ -0,0 +1,159 @@
+//! Typed access to commonly used [`world`](https://www.byond.com/docs/ref/#/world) vars.
+use std::collections::HashMap;
+
+use crate::{byond_string, prelude::*, Error};
+
+/// What OS the server is running on, corresponds to [`world.system_type`](https://www.byond.com/docs/ref/#/world/var/system_type)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemType {
+    MsWindows,
+    Unix,
+}
+
+/// Handle to DM's `world`, with typed getters and setters for its common vars.
+///
+/// Var names are looked up once and cached, so this is cheaper than going through [`ByondValue::read_var`].
+#[derive(Debug, Clone, Copy)]
+pub struct World(ByondValue);
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self(ByondValue::world())
+    }
+
+    /// Gets the underlying `world` value
+    pub fn as_value(&self) -> &ByondValue {
+        &self.0
+    }
+
+    fn read_coordinate(&self, name: u4c) -> Result<i16, Error> {
+        Ok(self.0.read_number_id(name)? as i16)
+    }
+
+    fn write_number(&mut self, name: u4c, value: f32) -> Result<(), Error> {
+        self.0.write_var_id(name, &value.into())
+    }
+
+    /// Corresponds to [`world.time`](https://www.byond.com/docs/ref/#/world/var/time), in deciseconds
+    pub fn time(&self) -> Result<f32, Error> {
+        self.0.read_number_id(byond_string!("time"))
+    }
+
+    /// Corresponds to [`world.timeofday`](https://www.byond.com/docs/ref/#/world/var/timeofday), in deciseconds
+    pub fn timeofday(&self) -> Result<f32, Error> {
+        self.0.read_number_id(byond_string!("timeofday"))
+    }
+
+    /// Corresponds to [`world.tick_usage`](https://www.byond.com/docs/ref/#/world/var/tick_usage), as a percentage
+    pub fn tick_usage(&self) -> Result<f32, Error> {
+        self.0.read_number_id(byond_string!("tick_usage"))
+    }
+
+    /// Corresponds to [`world.tick_lag`](https://www.byond.com/docs/ref/#/world/var/tick_lag), in deciseconds
+    pub fn tick_lag(&self) -> Result<f32, Error> {
+        self.0.read_number_id(byond_string!("tick_lag"))
+    }
+
+    pub fn set_tick_lag(&mut self, tick_lag: f32) -> Result<(), Error> {
+        self.write_number(byond_string!("tick_lag"), tick_lag)
+    }
+
+    /// Corresponds to [`world.cpu`](https://www.byond.com/docs/ref/#/world/var/cpu), as a percentage
+    pub fn cpu(&self) -> Result<f32, Error> {
+        self.0.read_number_id(byond_string!("cpu"))
+    }
+
+    /// Corresponds to [`world.fps`](https://www.byond.com/docs/ref/#/world/var/fps)
+    pub fn fps(&self) -> Result<f32, Error> {
+        self.0.read_number_id(byond_string!("fps"))
+    }
+
+    pub fn set_fps(&mut self, fps: f32) -> Result<(), Error> {
+        self.write_number(byond_string!("fps"), fps)
+    }
+
+    /// Corresponds to [`world.maxx`](https://www.byond.com/docs/ref/#/world/var/maxx)
+    pub fn maxx(&self) -> Result<i16, Error> {
+        self.read_coordinate(byond_string!("maxx"))
+    }
+
+    pub fn set_maxx(&mut self, maxx: i16) -> Result<(), Error> {
+        self.write_number(byond_string!("maxx"), maxx as f32)
+    }
+
+    /// Corresponds to [`world.maxy`](https://www.byond.com/docs/ref/#/world/var/maxy)
+    pub fn maxy(&self) -> Result<i16, Error> {
+        self.read_coordinate(byond_string!("maxy"))
+    }
+
+    pub fn set_maxy(&mut self, maxy: i16) -> Result<(), Error> {
+        self.write_number(byond_string!("maxy"), maxy as f32)
+    }
+
+    /// Corresponds to [`world.maxz`](https://www.byond.com/docs/ref/#/world/var/maxz)
+    pub fn maxz(&self) -> Result<i16, Error> {
+        self.read_coordinate(byond_string!("maxz"))
+    }
+
+    pub fn set_maxz(&mut self, maxz: i16) -> Result<(), Error> {
+        self.write_number(byond_string!("maxz"), maxz as f32)
+    }
+
+    /// Corresponds to [`world.name`](https://www.byond.com/docs/ref/#/world/var/name)
+    pub fn name(&self) -> Result<String, Error> {
+        self.0.read_string_id(byond_string!("name"))
+    }
+
+    pub fn set_name<T: Into<Vec<u8>>>(&mut self, name: T) -> Result<(), Error> {
+        let name = ByondValue::new_str(name)?;
+        self.0.write_var_id(byond_string!("name"), &name)
+    }
+
+    /// Corresponds to [`world.log`](https://www.byond.com/docs/ref/#/world/var/log)
+    pub fn log(&self) -> Result<ByondValue, Error> {
+        self.0.read_var_id(byond_string!("log"))
+    }
+
+    pub fn set_log(&mut self, log: &ByondValue) -> Result<(), Error> {
+        self.0.write_var_id(byond_string!("log"), log)
+    }
+
+    /// Corresponds to [`world.system_type`](https://www.byond.com/docs/ref/#/world/var/system_type)
+    pub fn system_type(&self) -> Result<SystemType, Error> {
+        match self
+            .0
+            .read_string_id(byond_string!("system_type"))?
+            .as_str()
+        {
+            "MS_WINDOWS" => Ok(SystemType::MsWindows),
+            "UNIX" => Ok(SystemType::Unix),
+            other => Err(Error::InvalidConversion {
+                expected: "SystemType",
+                actual: format!("{other:?}"),
+            }),
+        }
+    }
+
+    /// Corresponds to [`world.params`](https://www.byond.com/docs/ref/#/world/var/params)
+    /// Params given without a value map to an empty string.
+    pub fn params(&self) -> Result<HashMap<String, String>, Error> {
+        self.0
+            .read_var_id(byond_string!("params"))?
+            .iter()?
+            .map(|(key, value)| {
+                let value = if value.is_null() {
+                    String::new()
+                } else {
+                    value.get_string()?
+                };
+                Ok((key.get_string()?, value))
+            })
+            .collect()
+    }
+}